@@ -8,6 +8,12 @@ export interface PostProcessingConfig {
 	edgeThreshold: number;
 	/** Gaussian feathering radius for edge smoothing (default 2) */
 	featherRadius: number;
+	/** Poisson solver: 'multigrid' (default) or 'sor' */
+	poissonMethod: 'multigrid' | 'sor';
+	/** RMS residual at which the Poisson solve stops (default 1e-4) */
+	poissonTolerance: number;
+	/** V-cycles or SOR sweeps before giving up (default 0 = solver default) */
+	poissonMaxIterations: number;
 }
 
 export const DEFAULT_POST_PROCESSING_CONFIG: PostProcessingConfig = {
@@ -16,6 +22,9 @@ export const DEFAULT_POST_PROCESSING_CONFIG: PostProcessingConfig = {
 	guideEps: 0.01,
 	edgeThreshold: 10,
 	featherRadius: 2,
+	poissonMethod: 'multigrid',
+	poissonTolerance: 1e-4,
+	poissonMaxIterations: 0,
 };
 
 export interface PostProcessMessage {
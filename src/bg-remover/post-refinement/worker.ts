@@ -39,16 +39,20 @@ self.onmessage = async (e: MessageEvent) => {
 			const maskInput = new Uint8Array(maskRgba);
 			const originalInput = new Uint8Array(originalRgba);
 
-			const result: Uint8Array = wasmModule.post_process(
-				maskInput,
-				originalInput,
-				width,
-				height,
-				config.guideRadius,
-				config.guideEps,
-				config.edgeThreshold,
-				config.featherRadius,
-			);
+			// Prebuilt modules from before post_process_with_options only have
+			// the positional export, which uses the default Poisson settings
+			const result: Uint8Array = wasmModule.post_process_with_options
+				? wasmModule.post_process_with_options(maskInput, originalInput, width, height, config)
+				: wasmModule.post_process(
+						maskInput,
+						originalInput,
+						width,
+						height,
+						config.guideRadius,
+						config.guideEps,
+						config.edgeThreshold,
+						config.featherRadius,
+					);
 
 			const buffer = result.buffer;
 			self.postMessage(
@@ -1,21 +1,69 @@
 export interface PreProcessingConfig {
 	enabled: boolean;
+	/** Auto white balance before all other stages (default 'none') */
+	whiteBalance: 'none' | 'gray_world' | 'white_patch' | 'combined';
+	/** Auto levels percentile clip in percent per end; 0 disables (default 0) */
+	autoLevelsClip: number;
 	/** CLAHE contrast clip limit (default 2.0) */
 	claheClipLimit: number;
 	/** CLAHE grid tile count (default 8) */
 	claheGridSize: number;
-	/** Bilateral denoise radius (default 3, max 7) */
+	/** Channel CLAHE equalizes: luma ratio, perceptual lightness, or HSV value (default 'luma') */
+	claheColorSpace: 'luma' | 'oklab' | 'lab' | 'hsv';
+	/** Blend of the CLAHE result with the original, 0–1 (default 1) */
+	claheStrength: number;
+	/** Bilateral denoise radius (default 3, capped by denoiseRadiusCap) */
 	noiseKernelSize: number;
-	/** Laplacian sharpening strength (default 0.3) */
+	/** Denoise spatial sigma in pixels; 0 uses the radius (default 0) */
+	denoiseSigmaSpatial: number;
+	/** Denoise range sigma, 0–255 units (default 30) */
+	denoiseSigmaRange: number;
+	/** Denoise range metric (default 'max') */
+	denoiseRangeMetric: 'max' | 'euclidean' | 'luma';
+	/** Upper bound on the denoise radius (default 7) */
+	denoiseRadiusCap: number;
+	/** Denoise algorithm: separable/exact bilateral or non-local means (default 'bilateral') */
+	denoiseMethod: 'bilateral' | 'bilateral_full' | 'nlm';
+	/** NLM search window radius (default 7) */
+	nlmSearchRadius: number;
+	/** NLM patch radius (default 1) */
+	nlmPatchRadius: number;
+	/** NLM filtering strength, 0–255 units (default 10) */
+	nlmH: number;
+	/** Unsharp-mask amount (default 0.3) */
 	sharpenStrength: number;
+	/** Unsharp-mask Gaussian sigma in pixels (default 0.8) */
+	sharpenRadius: number;
+	/** Minimum detail in 0–255 levels that gets sharpened (default 0) */
+	sharpenThreshold: number;
+	/** Sharpen everywhere or only along edges (default 'usm') */
+	sharpenMode: 'usm' | 'edges';
+	/** Estimate noise/contrast/sharpness and pick denoise, CLAHE and sharpen settings (default false) */
+	autoParams: boolean;
 }
 
 export const DEFAULT_PRE_PROCESSING_CONFIG: PreProcessingConfig = {
 	enabled: true,
+	whiteBalance: 'none',
+	autoLevelsClip: 0,
 	claheClipLimit: 2.0,
 	claheGridSize: 8,
+	claheColorSpace: 'luma',
+	claheStrength: 1,
 	noiseKernelSize: 3,
+	denoiseSigmaSpatial: 0,
+	denoiseSigmaRange: 30,
+	denoiseRangeMetric: 'max',
+	denoiseRadiusCap: 7,
+	denoiseMethod: 'bilateral',
+	nlmSearchRadius: 7,
+	nlmPatchRadius: 1,
+	nlmH: 10,
 	sharpenStrength: 0.3,
+	sharpenRadius: 0.8,
+	sharpenThreshold: 0,
+	sharpenMode: 'usm',
+	autoParams: false,
 };
 
 export interface PreProcessMessage {
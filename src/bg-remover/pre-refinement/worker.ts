@@ -35,15 +35,20 @@ self.onmessage = async (e: MessageEvent) => {
 			const { rgba, width, height, config } = msg;
 			const input = new Uint8Array(rgba);
 
-			const result: Uint8Array = wasmModule.pre_process(
-				input,
-				width,
-				height,
-				config.claheClipLimit,
-				config.claheGridSize,
-				config.noiseKernelSize,
-				config.sharpenStrength,
-			);
+			// The WASM side reads the camelCase keys of PreProcessingConfig directly.
+			// Prebuilt modules from before pre_process_with_options only have the
+			// positional export, which uses the defaults for everything else
+			const result: Uint8Array = wasmModule.pre_process_with_options
+				? wasmModule.pre_process_with_options(input, width, height, config)
+				: wasmModule.pre_process(
+						input,
+						width,
+						height,
+						config.claheClipLimit,
+						config.claheGridSize,
+						config.noiseKernelSize,
+						config.sharpenStrength,
+					);
 
 			// Transfer the buffer back (zero-copy)
 			const buffer = result.buffer;
@@ -118,6 +118,7 @@ function sendMsg(
 export async function wasmOptimize(
 	imageData: { data: Uint8Array; width: number; height: number },
 	strength: number = 0.5,
+	denoiseMethod: 'bilateral' | 'nlm' = 'bilateral',
 ): Promise<{ data: Uint8Array; width: number; height: number }> {
 	const ready = await initCompressor();
 	if (!ready || !worker) return imageData;
@@ -131,6 +132,7 @@ export async function wasmOptimize(
 				width: imageData.width,
 				height: imageData.height,
 				strength,
+				denoiseMethod,
 			},
 			[buffer],
 		);
@@ -59,6 +59,7 @@ export async function compressImage(
 			const optimized = await wasmOptimize(
 				{ data: new Uint8Array(imageData.data.buffer), width, height },
 				config.optimizeStrength,
+				config.denoiseMethod,
 			);
 
 			// Apply quantization for PNG if requested
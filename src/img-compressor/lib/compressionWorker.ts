@@ -29,6 +29,7 @@ interface CompressorConfig {
 	format: 'jpeg' | 'png' | 'webp';
 	enableWasmOptimize: boolean;
 	optimizeStrength: number;
+	denoiseMethod: 'bilateral' | 'nlm';
 	maxColors: number;
 	verifySsim: boolean;
 	targetSize: number;
@@ -149,12 +150,22 @@ async function processCompress(id: number, file: File, config: CompressorConfig)
 			try {
 				postProgress('Optimizing', 50);
 				const input = new Uint8Array(imageData.data.buffer);
-				const optimized: Uint8Array = wasmModule.optimize_for_compression(
-					input,
-					width,
-					height,
-					config.optimizeStrength,
-				);
+				// Prebuilt modules from before optimize_for_compression_with_method
+				// only have the bilateral path
+				const optimized: Uint8Array = wasmModule.optimize_for_compression_with_method
+					? wasmModule.optimize_for_compression_with_method(
+							input,
+							width,
+							height,
+							config.optimizeStrength,
+							config.denoiseMethod,
+						)
+					: wasmModule.optimize_for_compression(
+							input,
+							width,
+							height,
+							config.optimizeStrength,
+						);
 
 				// Step 4b: Quantize colors for PNG if requested
 				let finalData = optimized;
@@ -9,6 +9,8 @@ export interface CompressorConfig {
 	enableWasmOptimize: boolean;
 	/** Strength of pre-compression optimization 0.0-1.0 */
 	optimizeStrength: number;
+	/** Denoise algorithm for optimization: fast bilateral or texture-preserving NLM */
+	denoiseMethod: 'bilateral' | 'nlm';
 	/** Max colors for PNG quantization (0 = disabled, 2-256) */
 	maxColors: number;
 	/** Enable SSIM quality verification */
@@ -24,6 +26,7 @@ export const DEFAULT_COMPRESSOR_CONFIG: CompressorConfig = {
 	format: 'jpeg',
 	enableWasmOptimize: true,
 	optimizeStrength: 0.5,
+	denoiseMethod: 'bilateral',
 	maxColors: 0,
 	verifySsim: false,
 	targetSize: 0,
@@ -39,6 +42,7 @@ export interface CompressWorkerMessage {
 	width?: number;
 	height?: number;
 	strength?: number;
+	denoiseMethod?: 'bilateral' | 'nlm';
 	// quantize
 	maxColors?: number;
 	// ssim
@@ -47,14 +47,19 @@ self.onmessage = async (e: MessageEvent) => {
 	try {
 		switch (msg.type) {
 			case 'optimize': {
-				const { id, rgba, width, height, strength } = msg;
+				const { id, rgba, width, height, strength, denoiseMethod } = msg;
 				const input = new Uint8Array(rgba);
-				const result: Uint8Array = wasmModule.optimize_for_compression(
-					input,
-					width,
-					height,
-					strength ?? 0.5,
-				);
+				// Prebuilt modules from before optimize_for_compression_with_method
+				// only have the bilateral path
+				const result: Uint8Array = wasmModule.optimize_for_compression_with_method
+					? wasmModule.optimize_for_compression_with_method(
+							input,
+							width,
+							height,
+							strength ?? 0.5,
+							denoiseMethod ?? 'bilateral',
+						)
+					: wasmModule.optimize_for_compression(input, width, height, strength ?? 0.5);
 				const buffer = result.buffer;
 				self.postMessage(
 					{ id, type: 'optimized', rgba: buffer, width, height },
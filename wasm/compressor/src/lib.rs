@@ -11,6 +11,7 @@ use wasm_bindgen::prelude::*;
 //
 // Algorithms:
 // - Bilateral denoise: Edge-preserving noise removal (noise compresses poorly)
+// - Non-local means denoise: Patch-based alternative that keeps fine texture
 // - Median-cut color quantization: Optimal palette with perceptual distance
 // - Floyd-Steinberg dithering: Eliminate banding in quantized images
 // - Per-row PNG filter selection: Minimize entropy for deflate compression
@@ -25,6 +26,20 @@ pub fn optimize_for_compression(
     width: u32,
     height: u32,
     strength: f32, // 0.0-1.0: compression aggressiveness
+) -> Vec<u8> {
+    optimize_for_compression_with_method(rgba, width, height, strength, "bilateral")
+}
+
+/// `optimize_for_compression` with a choice of denoiser.
+/// `denoise_method`: "bilateral" (fast) or "nlm" (non-local means, slower,
+/// preserves texture better at the same noise reduction).
+#[wasm_bindgen]
+pub fn optimize_for_compression_with_method(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    strength: f32,
+    denoise_method: &str,
 ) -> Vec<u8> {
     let w = width as usize;
     let h = height as usize;
@@ -36,9 +51,19 @@ pub fn optimize_for_compression(
 
     // Stage 1: Edge-preserving denoise (removes noise that inflates file size)
     // Stronger compression = more aggressive denoising
-    let denoise_radius = if strength > 0.3 { 2 } else { 1 };
-    let denoise_strength = (strength * 0.5).clamp(0.05, 0.4);
-    denoise::bilateral_denoise(&mut buf, w, h, denoise_radius, denoise_strength);
+    match denoise_method {
+        "nlm" => {
+            // Wider search and larger h as strength grows; 3×3 patches
+            let search_radius = if strength > 0.3 { 5 } else { 3 };
+            let nlm_h = 4.0 + 16.0 * strength.clamp(0.0, 1.0);
+            imaging::nlm::nlm_denoise(&mut buf, w, h, search_radius, 1, nlm_h);
+        }
+        _ => {
+            let denoise_radius = if strength > 0.3 { 2 } else { 1 };
+            let denoise_strength = (strength * 0.5).clamp(0.05, 0.4);
+            denoise::bilateral_denoise(&mut buf, w, h, denoise_radius, denoise_strength);
+        }
+    }
 
     // Stage 2: Chroma smoothing for areas with low luminance contrast
     // Human eyes are less sensitive to color than brightness
@@ -0,0 +1,493 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — Tone & Color Adjustments
+//
+// Applies a full adjustment set in one fused pass over the image:
+//
+//   1. White balance + exposure (linear light): per-channel gains.
+//      Temperature warms (R up, B down) or cools; tint shifts G against
+//      magenta. Gains are normalized to keep BT.709 luminance, so white
+//      balance doesn't change brightness. Exposure multiplies by 2^stops.
+//
+//   2. Levels (sRGB, perceptual): master then per channel
+//        v' = ((v − in_black) / (in_white − in_black))^(1/gamma)
+//             · (out_white − out_black) + out_black
+//
+//   3. Contrast (sRGB): positive blends toward a smoothstep S-curve
+//      around mid-gray, negative flattens toward mid-gray.
+//
+//   4. Tone curves (sRGB): monotone cubic interpolation through control
+//      points (Fritsch & Carlson, 1980) — no overshoot between points,
+//      so a monotone set of points never inverts tones. Master then
+//      per channel.
+//
+//   5. Saturation + vibrance (linear light, around BT.709 luminance Y):
+//        c' = Y + f · (c − Y),  f = (1 + saturation) · (1 + vibrance · (1 − s))
+//      with s = (max − min) / max, so vibrance boosts muted colors more
+//      than already saturated ones. f is capped so no channel leaves
+//      [0, 1]: colors stop at the gamut edge with their hue intact.
+//
+// Stages 1–4 act on each channel independently, so they're baked into
+// one 256-entry LUT per channel. Stage 5 mixes channels and runs per
+// pixel, with a 4096-entry LUT for the final linear → sRGB encode.
+// Alpha is passed through unchanged.
+//
+// References:
+//   - Fritsch & Carlson, "Monotone Piecewise Cubic Interpolation",
+//     SIAM J. Numerical Analysis 17(2), 1980
+//   - IEC 61966-2-1:1999 (sRGB transfer, via alpha.rs)
+// ═══════════════════════════════════════════════════════════════════
+
+use imaging::srgb::{
+    build_srgb_to_linear_lut, linear_to_srgb, linear_to_srgb_f32, pow_f32, srgb_to_linear_f32,
+};
+
+/// White-balance gain range: ±1 temperature/tint = ±0.25 stops per channel.
+const WB_STOPS: f32 = 0.25;
+/// Resolution of the linear → sRGB encode LUT used by the color stage.
+const ENCODE_LUT_LEN: usize = 4096;
+/// Floats per levels group: in_black, in_white, gamma, out_black, out_white.
+pub const LEVELS_STRIDE: usize = 5;
+
+/// BT.709 luminance of linear RGB.
+#[inline]
+fn luminance(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+/// Input/output range remap with gamma, all ends in [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Levels {
+    pub in_black: f32,
+    pub in_white: f32,
+    pub gamma: f32,
+    pub out_black: f32,
+    pub out_white: f32,
+}
+
+impl Levels {
+    pub const IDENTITY: Levels = Levels {
+        in_black: 0.0,
+        in_white: 1.0,
+        gamma: 1.0,
+        out_black: 0.0,
+        out_white: 1.0,
+    };
+
+    /// From `[in_black, in_white, gamma, out_black, out_white]` in 0–255 units
+    /// (gamma unitless).
+    pub fn from_slice(v: &[f32]) -> Self {
+        Levels {
+            in_black: v[0] / 255.0,
+            in_white: v[1] / 255.0,
+            gamma: v[2],
+            out_black: v[3] / 255.0,
+            out_white: v[4] / 255.0,
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    fn apply(&self, v: f32) -> f32 {
+        let span = (self.in_white - self.in_black).max(1e-4);
+        let t = ((v - self.in_black) / span).clamp(0.0, 1.0);
+        let t = if self.gamma > 0.0 && self.gamma != 1.0 {
+            pow_f32(t, 1.0 / self.gamma)
+        } else {
+            t
+        };
+        self.out_black + t * (self.out_white - self.out_black)
+    }
+}
+
+/// Monotone cubic tone curve through control points in [0, 1]².
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    slopes: Vec<f32>,
+}
+
+impl Curve {
+    /// Build from (x, y) points; `None` with fewer than 2 distinct x values.
+    pub fn new(points: &[(f32, f32)]) -> Option<Self> {
+        let mut pts: Vec<(f32, f32)> = points
+            .iter()
+            .map(|&(x, y)| (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+            .collect();
+        pts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        pts.dedup_by(|a, b| (a.0 - b.0).abs() < 1e-6);
+        if pts.len() < 2 {
+            return None;
+        }
+        let xs: Vec<f32> = pts.iter().map(|p| p.0).collect();
+        let ys: Vec<f32> = pts.iter().map(|p| p.1).collect();
+        let n = xs.len();
+
+        // Secant slopes, then Fritsch–Carlson tangents
+        let delta: Vec<f32> = (0..n - 1)
+            .map(|i| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]))
+            .collect();
+        let mut slopes = vec![0.0f32; n];
+        slopes[0] = delta[0];
+        slopes[n - 1] = delta[n - 2];
+        for i in 1..n - 1 {
+            slopes[i] = if delta[i - 1] * delta[i] <= 0.0 {
+                0.0
+            } else {
+                (delta[i - 1] + delta[i]) * 0.5
+            };
+        }
+        for i in 0..n - 1 {
+            if delta[i] == 0.0 {
+                slopes[i] = 0.0;
+                slopes[i + 1] = 0.0;
+                continue;
+            }
+            let a = slopes[i] / delta[i];
+            let b = slopes[i + 1] / delta[i];
+            let s = a * a + b * b;
+            if s > 9.0 {
+                let tau = 3.0 / s.sqrt();
+                slopes[i] = tau * a * delta[i];
+                slopes[i + 1] = tau * b * delta[i];
+            }
+        }
+        Some(Curve { xs, ys, slopes })
+    }
+
+    /// Curves from `[n, x0, y0, x1, y1, …]` groups (0–255 units): master,
+    /// then R, G, B. Missing or short groups mean "no curve".
+    pub fn parse_set(v: &[f32]) -> [Option<Curve>; 4] {
+        let mut out: [Option<Curve>; 4] = Default::default();
+        let mut pos = 0;
+        for slot in out.iter_mut() {
+            let Some(&n) = v.get(pos) else { break };
+            let n = n.max(0.0) as usize;
+            let end = (pos + 1 + 2 * n).min(v.len());
+            let points: Vec<(f32, f32)> = v[pos + 1..end]
+                .chunks_exact(2)
+                .map(|p| (p[0] / 255.0, p[1] / 255.0))
+                .collect();
+            *slot = Curve::new(&points);
+            pos = end;
+        }
+        out
+    }
+
+    pub fn eval(&self, x: f32) -> f32 {
+        let n = self.xs.len();
+        if x <= self.xs[0] {
+            return self.ys[0];
+        }
+        if x >= self.xs[n - 1] {
+            return self.ys[n - 1];
+        }
+        let i = self.xs.partition_point(|&v| v <= x) - 1;
+        let h = self.xs[i + 1] - self.xs[i];
+        let t = (x - self.xs[i]) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        // Cubic Hermite basis
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+        (h00 * self.ys[i]
+            + h10 * h * self.slopes[i]
+            + h01 * self.ys[i + 1]
+            + h11 * h * self.slopes[i + 1])
+            .clamp(0.0, 1.0)
+    }
+}
+
+/// A complete adjustment set. `Default` is the identity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Adjustments {
+    /// Stops, e.g. +1 doubles linear light.
+    pub exposure: f32,
+    /// −1 (flat gray) … 0 … 1 (full S-curve).
+    pub contrast: f32,
+    /// Master, R, G, B.
+    pub levels: [Levels; 4],
+    /// Master, R, G, B.
+    pub curves: [Option<Curve>; 4],
+    /// −1 (grayscale) … 0 … 1 (double chroma).
+    pub saturation: f32,
+    /// −1 … 1, weighted toward muted colors.
+    pub vibrance: f32,
+    /// −1 (cool) … 1 (warm).
+    pub temperature: f32,
+    /// −1 (green) … 1 (magenta).
+    pub tint: f32,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Adjustments {
+            exposure: 0.0,
+            contrast: 0.0,
+            levels: [Levels::IDENTITY; 4],
+            curves: Default::default(),
+            saturation: 0.0,
+            vibrance: 0.0,
+            temperature: 0.0,
+            tint: 0.0,
+        }
+    }
+}
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        self.exposure == 0.0
+            && self.contrast == 0.0
+            && self.levels.iter().all(Levels::is_identity)
+            && self.curves.iter().all(Option::is_none)
+            && !self.has_color_stage()
+            && self.temperature == 0.0
+            && self.tint == 0.0
+    }
+
+    fn has_color_stage(&self) -> bool {
+        self.saturation != 0.0 || self.vibrance != 0.0
+    }
+
+    /// Linear-light gain per channel: white balance × exposure.
+    fn gains(&self) -> [f32; 3] {
+        let r = pow_f32(2.0, WB_STOPS * self.temperature);
+        let g = pow_f32(2.0, -WB_STOPS * self.tint);
+        let b = pow_f32(2.0, -WB_STOPS * self.temperature);
+        let norm = pow_f32(2.0, self.exposure) / luminance([r, g, b]);
+        [r * norm, g * norm, b * norm]
+    }
+
+    /// Stages 2–4 on one sRGB channel value (0–1).
+    fn tone(&self, channel: usize, v: f32) -> f32 {
+        let mut v = self.levels[0].apply(v);
+        v = self.levels[channel + 1].apply(v);
+
+        if self.contrast > 0.0 {
+            let s = v * v * (3.0 - 2.0 * v);
+            v += self.contrast.min(1.0) * (s - v);
+        } else if self.contrast < 0.0 {
+            v = 0.5 + (v - 0.5) * (1.0 + self.contrast.max(-1.0));
+        }
+
+        if let Some(c) = &self.curves[0] {
+            v = c.eval(v);
+        }
+        if let Some(c) = &self.curves[channel + 1] {
+            v = c.eval(v);
+        }
+        v
+    }
+
+    /// Stage 5 on linear RGB.
+    fn color(&self, c: [f32; 3]) -> [f32; 3] {
+        let y = luminance(c);
+        let max = c[0].max(c[1]).max(c[2]);
+        let min = c[0].min(c[1]).min(c[2]);
+        let sat = if max > 0.0 { (max - min) / max } else { 0.0 };
+        let mut f = ((1.0 + self.saturation) * (1.0 + self.vibrance * (1.0 - sat))).max(0.0);
+
+        // Largest factor that keeps every channel inside [0, 1]
+        for &v in &c {
+            let d = v - y;
+            if d > 1e-6 {
+                f = f.min((1.0 - y) / d);
+            } else if d < -1e-6 {
+                f = f.min(y / -d);
+            }
+        }
+        [y + f * (c[0] - y), y + f * (c[1] - y), y + f * (c[2] - y)]
+    }
+
+    /// Adjust one unquantized sRGB color (0–1 per channel).
+    pub fn apply_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let gains = self.gains();
+        let mut lin = [0.0f32; 3];
+        for c in 0..3 {
+            let l = (srgb_to_linear_f32(rgb[c].clamp(0.0, 1.0)) * gains[c]).min(1.0);
+            lin[c] = srgb_to_linear_f32(self.tone(c, linear_to_srgb_f32(l).clamp(0.0, 1.0)));
+        }
+        if self.has_color_stage() {
+            lin = self.color(lin);
+        }
+        [
+            linear_to_srgb_f32(lin[0]).clamp(0.0, 1.0),
+            linear_to_srgb_f32(lin[1]).clamp(0.0, 1.0),
+            linear_to_srgb_f32(lin[2]).clamp(0.0, 1.0),
+        ]
+    }
+
+    /// Adjust an RGBA buffer (alpha untouched).
+    pub fn apply(&self, rgba: &[u8]) -> Vec<u8> {
+        if self.is_identity() {
+            return rgba.to_vec();
+        }
+
+        // Stages 1–4 per channel, as linear light
+        let decode = build_srgb_to_linear_lut();
+        let gains = self.gains();
+        let mut tone_lut = [[0.0f32; 256]; 3];
+        for (c, lut) in tone_lut.iter_mut().enumerate() {
+            for (i, v) in lut.iter_mut().enumerate() {
+                let l = (decode[i] * gains[c]).min(1.0);
+                *v = srgb_to_linear_f32(self.tone(c, linear_to_srgb_f32(l).clamp(0.0, 1.0)));
+            }
+        }
+
+        let mut out = rgba.to_vec();
+        if !self.has_color_stage() {
+            let byte_lut: Vec<[u8; 256]> = tone_lut
+                .iter()
+                .map(|lut| {
+                    let mut b = [0u8; 256];
+                    for (o, &v) in b.iter_mut().zip(lut.iter()) {
+                        *o = linear_to_srgb(v);
+                    }
+                    b
+                })
+                .collect();
+            for px in out.chunks_exact_mut(4) {
+                for c in 0..3 {
+                    px[c] = byte_lut[c][px[c] as usize];
+                }
+            }
+            return out;
+        }
+
+        let scale = (ENCODE_LUT_LEN - 1) as f32;
+        let encode: Vec<u8> = (0..ENCODE_LUT_LEN)
+            .map(|i| linear_to_srgb(i as f32 / scale))
+            .collect();
+        for px in out.chunks_exact_mut(4) {
+            let lin = self.color([
+                tone_lut[0][px[0] as usize],
+                tone_lut[1][px[1] as usize],
+                tone_lut[2][px[2] as usize],
+            ]);
+            for c in 0..3 {
+                px[c] = encode[(lin[c].clamp(0.0, 1.0) * scale + 0.5) as usize];
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(v: u8) -> Vec<u8> {
+        vec![v, v, v, 200]
+    }
+
+    #[test]
+    fn test_identity_is_exact() {
+        let rgba: Vec<u8> = (0..=255u8).flat_map(|v| [v, 255 - v, v / 2, v]).collect();
+        assert_eq!(Adjustments::default().apply(&rgba), rgba);
+    }
+
+    #[test]
+    fn test_exposure_one_stop_doubles_linear() {
+        let adj = Adjustments {
+            exposure: 1.0,
+            ..Default::default()
+        };
+        // sRGB 118 ≈ 0.181 linear → 0.362 linear ≈ sRGB 161
+        let out = adj.apply(&gray(118));
+        assert!((out[0] as i32 - 161).abs() <= 1, "got {}", out[0]);
+        assert_eq!(out[3], 200);
+    }
+
+    #[test]
+    fn test_levels_stretch_input_range() {
+        let adj = Adjustments {
+            levels: [
+                Levels::from_slice(&[50.0, 200.0, 1.0, 0.0, 255.0]),
+                Levels::IDENTITY,
+                Levels::IDENTITY,
+                Levels::IDENTITY,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(adj.apply(&gray(50))[0], 0);
+        assert_eq!(adj.apply(&gray(200))[0], 255);
+        assert!((adj.apply(&gray(125))[0] as i32 - 128).abs() <= 1);
+    }
+
+    #[test]
+    fn test_curve_hits_points_and_is_monotone() {
+        let curves = Curve::parse_set(&[3.0, 0.0, 0.0, 128.0, 200.0, 255.0, 255.0]);
+        assert!(curves[0].is_some() && curves[1].is_none());
+        let adj = Adjustments {
+            curves,
+            ..Default::default()
+        };
+        let ramp: Vec<u8> = (0..=255u8).flat_map(|v| [v, v, v, 255]).collect();
+        let out = adj.apply(&ramp);
+        assert!((out[128 * 4] as i32 - 200).abs() <= 1);
+        for i in 1..256 {
+            assert!(out[i * 4] >= out[(i - 1) * 4]);
+        }
+    }
+
+    #[test]
+    fn test_desaturate_and_temperature() {
+        let adj = Adjustments {
+            saturation: -1.0,
+            ..Default::default()
+        };
+        let out = adj.apply(&[200, 60, 30, 255]);
+        assert!(out[0] == out[1] && out[1] == out[2]);
+
+        let warm = Adjustments {
+            temperature: 1.0,
+            ..Default::default()
+        };
+        let out = warm.apply(&gray(128));
+        assert!(out[0] > out[1] && out[1] > out[2]);
+    }
+
+    #[test]
+    fn test_saturation_stays_in_gamut_with_hue() {
+        let adj = Adjustments {
+            saturation: 1.0,
+            vibrance: 1.0,
+            ..Default::default()
+        };
+        let out = adj.apply(&[220, 120, 60, 255]);
+        // Channel order (hue) is kept even where chroma hits the gamut edge
+        assert!(out[0] > out[1] && out[1] > out[2]);
+    }
+
+    #[test]
+    fn test_cube_export_matches_direct_apply() {
+        use crate::cube::{apply_cube, parse_cube, write_cube, LutInterpolation};
+        let adj = Adjustments {
+            exposure: 0.3,
+            contrast: 0.4,
+            saturation: 0.3,
+            temperature: -0.5,
+            ..Default::default()
+        };
+        let lut = parse_cube(&write_cube("look", 33, |rgb| adj.apply_rgb(rgb))).unwrap();
+        let rgba: Vec<u8> = (0..=255u8)
+            .flat_map(|v| [v, v.wrapping_mul(7), 255 - v, 255])
+            .collect();
+        let direct = adj.apply(&rgba);
+        let graded = apply_cube(&rgba, &lut, LutInterpolation::Tetrahedral, 1.0);
+        // Channels driven to ~0 by the saturation gamut cap sit on the steep
+        // end of the sRGB curve, so single samples can miss by a few levels
+        let diffs: Vec<i32> = direct
+            .iter()
+            .zip(&graded)
+            .map(|(a, b)| (*a as i32 - *b as i32).abs())
+            .collect();
+        let mean = diffs.iter().sum::<i32>() as f32 / diffs.len() as f32;
+        assert!(mean < 1.0, "mean error {mean}");
+        assert!(diffs.iter().filter(|&&d| d > 4).count() * 50 < diffs.len());
+    }
+}
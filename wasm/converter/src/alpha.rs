@@ -15,98 +15,7 @@
 //   measurement and management — Part 2-1: Default RGB colour space — sRGB
 // ═══════════════════════════════════════════════════════════════════
 
-/// Build the sRGB → linear lookup table (256 entries).
-///
-/// Transfer function (IEC 61966-2-1):
-///   if C_srgb ≤ 0.04045 → C_linear = C_srgb / 12.92
-///   else                → C_linear = ((C_srgb + 0.055) / 1.055)^2.4
-fn build_srgb_to_linear_lut() -> [f32; 256] {
-    let mut lut = [0.0f32; 256];
-    let mut i = 0;
-    while i < 256 {
-        let s = i as f32 / 255.0;
-        lut[i] = if s <= 0.04045 {
-            s / 12.92
-        } else {
-            pow_f32((s + 0.055) / 1.055, 2.4)
-        };
-        i += 1;
-    }
-    lut
-}
-
-/// Linear → sRGB conversion for a single channel.
-///
-/// Inverse transfer function:
-///   if C_linear ≤ 0.0031308 → C_srgb = 12.92 × C_linear
-///   else                    → C_srgb = 1.055 × C_linear^(1/2.4) − 0.055
-#[inline]
-fn linear_to_srgb(c: f32) -> u8 {
-    let s = if c <= 0.0031308 {
-        12.92 * c
-    } else {
-        1.055 * pow_f32(c, 1.0 / 2.4) - 0.055
-    };
-    (s * 255.0 + 0.5).clamp(0.0, 255.0) as u8
-}
-
-/// Compute `base^exp` without depending on `std::f32::powf` which may pull
-/// in libm. We use the exp-log identity: base^exp = exp2(exp × log2(base)).
-///
-/// For our use case (sRGB gamma ≈ 2.4, values in [0,1]) the precision of
-/// the hardware-friendly exp2/log2 path is more than sufficient (< 0.5 LSB
-/// error after quantization to u8).
-#[inline]
-fn pow_f32(base: f32, exp: f32) -> f32 {
-    if base <= 0.0 {
-        return 0.0;
-    }
-    // Use the identity: x^y = 2^(y * log2(x))
-    f32_exp2(exp * f32_log2(base))
-}
-
-/// Fast log2 approximation using IEEE 754 float bit manipulation.
-///
-/// Based on the observation that the exponent field of an IEEE 754 float
-/// is approximately log2 of the value. A linear correction term improves
-/// accuracy to ~0.06% max relative error over [0.001, 1.0].
-#[inline]
-fn f32_log2(x: f32) -> f32 {
-    if x <= 0.0 {
-        return f32::NEG_INFINITY;
-    }
-    let bits = x.to_bits() as i32;
-    let exponent = ((bits >> 23) & 0xFF) - 127;
-    let mantissa_bits = (bits & 0x7FFFFF) | 0x3F800000;
-    let m = f32::from_bits(mantissa_bits as u32);
-    // Minimax polynomial for log2(m) over [1, 2)
-    let log2_m = -3.4436006 + m * (5.5765866 + m * (-3.6301503 + m * 1.4974994));
-    exponent as f32 + log2_m
-}
-
-/// Fast exp2 approximation using IEEE 754 float bit manipulation.
-///
-/// Splits input into integer and fractional parts, uses bit manipulation
-/// for the integer part and a polynomial for the fractional part.
-/// Accuracy: < 0.1% relative error over [-10, 10].
-#[inline]
-fn f32_exp2(x: f32) -> f32 {
-    if x < -126.0 {
-        return 0.0;
-    }
-    if x > 128.0 {
-        return f32::INFINITY;
-    }
-    let floor = x.floor();
-    let frac = x - floor;
-    let int_part = floor as i32;
-    // Polynomial approximation for 2^frac over [0, 1)
-    let frac_bits =
-        (1.0 + frac * (0.6931472 + frac * (0.2402265 + frac * (0.0554913 + frac * 0.0096695))))
-            * (1u32 << 23) as f32;
-    let bits = (frac_bits as u32).wrapping_add(((int_part + 127) as u32) << 23);
-    f32::from_bits(bits)
-}
+use imaging::srgb::{build_srgb_to_linear_lut, linear_to_srgb};
 
 /// Composite RGBA over a solid background using gamma-correct blending.
 ///
@@ -117,14 +26,7 @@ fn f32_exp2(x: f32) -> f32 {
 ///
 /// Fast paths for fully opaque (α = 255) and fully transparent (α = 0)
 /// pixels avoid the expensive linearization/delinearization round-trip.
-pub fn composite_over(
-    rgba: &[u8],
-    w: usize,
-    h: usize,
-    bg_r: u8,
-    bg_g: u8,
-    bg_b: u8,
-) -> Vec<u8> {
+pub fn composite_over(rgba: &[u8], w: usize, h: usize, bg_r: u8, bg_g: u8, bg_b: u8) -> Vec<u8> {
     let lut = build_srgb_to_linear_lut();
     let bg_r_lin = lut[bg_r as usize];
     let bg_g_lin = lut[bg_g as usize];
@@ -207,19 +109,4 @@ mod tests {
         assert!(out[2] > 100 && out[2] < 200); // B moderate
         assert_eq!(out[3], 255);
     }
-
-    #[test]
-    fn test_srgb_roundtrip() {
-        let lut = build_srgb_to_linear_lut();
-        for i in 0..=255u8 {
-            let linear = lut[i as usize];
-            let back = linear_to_srgb(linear);
-            assert!(
-                (back as i16 - i as i16).unsigned_abs() <= 1,
-                "sRGB roundtrip failed for {}: got {}",
-                i,
-                back
-            );
-        }
-    }
 }
@@ -0,0 +1,141 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — Compositing Exports
+//
+// wasm-bindgen wrappers for placing a cutout into a scene: compositing onto
+// a background image (background.rs) and synthetic shadows (shadow.rs).
+// Plain solid-color compositing stays in lib.rs as `alpha_composite`.
+// ═══════════════════════════════════════════════════════════════════
+
+use crate::{background, shadow};
+use wasm_bindgen::prelude::*;
+
+// ─── Background Image ───────────────────────────────────────────────────────
+
+/// Composite RGBA pixels onto a background image.
+///
+/// The background is scaled to `placement` = "fill" (cover the canvas,
+/// center-cropped) or "fit" (contained and centered; the uncovered area keeps
+/// the cutout's own alpha). Output has the cutout's dimensions.
+///
+/// Optional passes, each disabled at 0.0:
+///   - `light_wrap`: 0.0–1.0 strength of background light screened onto the
+///     subject edge, spread over `light_wrap_radius` pixels
+///   - `harmonize`: 0.0–1.0 strength of matching the subject's color
+///     statistics to the background
+///
+/// All blending is gamma-correct (linear light), like `alpha_composite`.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn alpha_composite_image(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    bg_rgba: &[u8],
+    bg_width: u32,
+    bg_height: u32,
+    placement: &str,
+    light_wrap: f32,
+    light_wrap_radius: u32,
+    harmonize: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    let bw = bg_width as usize;
+    let bh = bg_height as usize;
+    if rgba.len() != w * h * 4
+        || bg_rgba.len() != bw * bh * 4
+        || w == 0
+        || h == 0
+        || bw == 0
+        || bh == 0
+    {
+        return rgba.to_vec();
+    }
+    background::composite_onto_image(
+        rgba,
+        w,
+        h,
+        bg_rgba,
+        bw,
+        bh,
+        background::Placement::from_name(placement),
+        light_wrap,
+        light_wrap_radius as usize,
+        harmonize,
+    )
+}
+
+// ─── Shadows ────────────────────────────────────────────────────────────────
+
+/// Add synthetic shadows beneath an RGBA cutout.
+///
+/// Shadows are generated from the cutout's alpha, tinted `color_r/g/b` and
+/// composited beneath the subject in linear light. Each layer is disabled
+/// when its opacity is 0.0:
+///   - Drop shadow: alpha shifted by (`drop_offset_x`, `drop_offset_y`),
+///     blurred over `drop_blur` pixels
+///   - Ground shadow: silhouette laid onto the floor plane through the
+///     subject's base and seen in perspective. `ground_length` is the
+///     shadow length on the floor relative to the subject height (higher =
+///     lower sun; negative casts it toward the viewer), `ground_skew` its
+///     sideways lean, `ground_horizon` the horizon height above the base in
+///     subject heights (1–3 typical; 0 = no perspective). Crisp and dark at
+///     the contact point, softening up to `ground_blur` pixels far away.
+///
+/// Returns RGBA of the same size; pixels outside subject and shadow stay
+/// transparent, so the result can go through `alpha_composite`.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn add_shadows(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    color_r: u8,
+    color_g: u8,
+    color_b: u8,
+    drop_opacity: f32,
+    drop_offset_x: i32,
+    drop_offset_y: i32,
+    drop_blur: u32,
+    ground_opacity: f32,
+    ground_skew: f32,
+    ground_length: f32,
+    ground_horizon: f32,
+    ground_blur: u32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return rgba.to_vec();
+    }
+
+    let alpha: Vec<f32> = rgba.chunks_exact(4).map(|p| p[3] as f32 / 255.0).collect();
+    let mut layer = vec![0.0f32; w * h];
+    if drop_opacity > 0.0 {
+        let drop = shadow::drop_shadow_layer(
+            &alpha,
+            w,
+            h,
+            drop_offset_x as isize,
+            drop_offset_y as isize,
+            drop_blur as f32,
+            drop_opacity.min(1.0),
+        );
+        shadow::merge_layers(&mut layer, &drop);
+    }
+    if ground_opacity > 0.0 {
+        let ground = shadow::ground_shadow_layer(
+            &alpha,
+            w,
+            h,
+            ground_skew,
+            ground_length,
+            ground_horizon,
+            ground_blur as f32,
+            ground_opacity.min(1.0),
+        );
+        shadow::merge_layers(&mut layer, &ground);
+    }
+
+    shadow::composite_shadow(rgba, &layer, [color_r, color_g, color_b])
+}
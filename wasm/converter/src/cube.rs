@@ -0,0 +1,414 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — .cube LUT Color Grading
+//
+//   1. Parsing and applying .cube LUTs (Adobe / DaVinci Resolve format)
+//      1D LUTs (per-channel curves, linear interpolation) and 3D LUTs
+//      (red varies fastest, then green, then blue). A file may carry a
+//      1D shaper followed by a 3D cube; the 1D stage is applied first.
+//      Inputs are remapped from DOMAIN_MIN..DOMAIN_MAX (or Resolve's
+//      LUT_*_INPUT_RANGE) to the table. 3D lookups use either:
+//        - Trilinear: 8 corners of the enclosing cell
+//        - Tetrahedral: 4 corners of one of the 6 tetrahedra the cell
+//          splits into along its gray diagonal — cheaper, and neutral
+//          grays interpolate only along the gray axis
+//      The graded color is blended with the original by `strength`.
+//
+//   2. .cube export: samples any color transform (e.g. an adjustment
+//      set) on an N³ grid and writes it as a 3D .cube file.
+//
+// Reference:
+//   - Adobe, "Cube LUT Specification", Version 1.0, 2013
+//   - Kasson, Nin & Plouffe, "Performing color space conversions with
+//     three-dimensional linear interpolation", J. Electronic Imaging 4(3),
+//     1995 (tetrahedral interpolation)
+// ═══════════════════════════════════════════════════════════════════
+
+/// Largest accepted LUT_3D_SIZE: 129³ rows is already ~60 MB of text, and
+/// no common tool writes more.
+const MAX_3D_SIZE: usize = 129;
+/// Largest exported LUT_3D_SIZE (65³ rows, ~7.7 MB of text).
+pub const MAX_EXPORT_SIZE: usize = 65;
+/// Largest accepted LUT_1D_SIZE.
+const MAX_1D_SIZE: usize = 65536;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LutInterpolation {
+    Trilinear,
+    Tetrahedral,
+}
+
+impl LutInterpolation {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "trilinear" => LutInterpolation::Trilinear,
+            _ => LutInterpolation::Tetrahedral,
+        }
+    }
+}
+
+/// One table of a .cube file with its input domain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LutTable {
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    pub data: Vec<[f32; 3]>,
+}
+
+impl LutTable {
+    /// Input value → fractional table coordinate in [0, size − 1].
+    #[inline]
+    fn coord(&self, v: f32, c: usize) -> f32 {
+        let span = self.domain_max[c] - self.domain_min[c];
+        let t = if span > 0.0 {
+            (v - self.domain_min[c]) / span
+        } else {
+            0.0
+        };
+        t.clamp(0.0, 1.0) * (self.size - 1) as f32
+    }
+}
+
+/// A parsed .cube file: optional 1D shaper, optional 3D cube (at least one).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub title: String,
+    pub lut_1d: Option<LutTable>,
+    pub lut_3d: Option<LutTable>,
+}
+
+/// Parse the text of a .cube file.
+pub fn parse_cube(text: &str) -> Result<CubeLut, String> {
+    let mut title = String::new();
+    let mut size_1d = 0usize;
+    let mut size_3d = 0usize;
+    let mut domain_min = [0.0f32; 3];
+    let mut domain_max = [1.0f32; 3];
+    let mut range_1d: Option<(f32, f32)> = None;
+    let mut range_3d: Option<(f32, f32)> = None;
+    let mut rows: Vec<[f32; 3]> = Vec::new();
+
+    let parse_f = |tok: &str, line_no: usize| -> Result<f32, String> {
+        tok.parse::<f32>()
+            .map_err(|_| format!("line {line_no}: invalid number '{tok}'"))
+    };
+
+    for (i, raw) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let keyword = parts.next().unwrap_or_default();
+        let args: Vec<&str> = parts.collect();
+        let floats = |n: usize| -> Result<Vec<f32>, String> {
+            if args.len() != n {
+                return Err(format!("line {line_no}: {keyword} expects {n} values"));
+            }
+            args.iter().map(|t| parse_f(t, line_no)).collect()
+        };
+        match keyword {
+            "TITLE" => {
+                title = line["TITLE".len()..].trim().trim_matches('"').to_string();
+            }
+            "LUT_1D_SIZE" | "LUT_3D_SIZE" => {
+                let n: usize = args
+                    .first()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(|| format!("line {line_no}: invalid {keyword}"))?;
+                let max = if keyword == "LUT_1D_SIZE" {
+                    MAX_1D_SIZE
+                } else {
+                    MAX_3D_SIZE
+                };
+                if !(2..=max).contains(&n) {
+                    return Err(format!(
+                        "line {line_no}: {keyword} {n} out of range 2–{max}"
+                    ));
+                }
+                if keyword == "LUT_1D_SIZE" {
+                    size_1d = n;
+                } else {
+                    size_3d = n;
+                }
+            }
+            "DOMAIN_MIN" => {
+                let v = floats(3)?;
+                domain_min = [v[0], v[1], v[2]];
+            }
+            "DOMAIN_MAX" => {
+                let v = floats(3)?;
+                domain_max = [v[0], v[1], v[2]];
+            }
+            "LUT_1D_INPUT_RANGE" => {
+                let v = floats(2)?;
+                range_1d = Some((v[0], v[1]));
+            }
+            "LUT_3D_INPUT_RANGE" => {
+                let v = floats(2)?;
+                range_3d = Some((v[0], v[1]));
+            }
+            _ if keyword.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => {
+                if args.len() != 2 {
+                    return Err(format!("line {line_no}: expected 3 values per row"));
+                }
+                rows.push([
+                    parse_f(keyword, line_no)?,
+                    parse_f(args[0], line_no)?,
+                    parse_f(args[1], line_no)?,
+                ]);
+            }
+            // Unknown keywords are skipped, as the spec allows
+            _ => {}
+        }
+    }
+
+    if size_1d == 0 && size_3d == 0 {
+        return Err("missing LUT_1D_SIZE or LUT_3D_SIZE".to_string());
+    }
+    let len_1d = size_1d;
+    let len_3d = size_3d * size_3d * size_3d;
+    if rows.len() != len_1d + len_3d {
+        return Err(format!(
+            "expected {} table rows, found {}",
+            len_1d + len_3d,
+            rows.len()
+        ));
+    }
+
+    let domain = |range: Option<(f32, f32)>| match range {
+        Some((lo, hi)) => ([lo; 3], [hi; 3]),
+        None => (domain_min, domain_max),
+    };
+    let lut_1d = (size_1d > 0).then(|| {
+        let (min, max) = domain(range_1d);
+        LutTable {
+            size: size_1d,
+            domain_min: min,
+            domain_max: max,
+            data: rows[..len_1d].to_vec(),
+        }
+    });
+    let lut_3d = (size_3d > 0).then(|| {
+        let (min, max) = domain(range_3d);
+        LutTable {
+            size: size_3d,
+            domain_min: min,
+            domain_max: max,
+            data: rows[len_1d..].to_vec(),
+        }
+    });
+
+    Ok(CubeLut {
+        title,
+        lut_1d,
+        lut_3d,
+    })
+}
+
+/// Per-channel linear interpolation in a 1D table.
+fn sample_1d(lut: &LutTable, rgb: [f32; 3]) -> [f32; 3] {
+    let mut out = [0.0f32; 3];
+    for c in 0..3 {
+        let x = lut.coord(rgb[c], c);
+        let i0 = (x as usize).min(lut.size - 2);
+        let t = x - i0 as f32;
+        out[c] = lut.data[i0][c] + t * (lut.data[i0 + 1][c] - lut.data[i0][c]);
+    }
+    out
+}
+
+/// Interpolated lookup in a 3D table.
+fn sample_3d(lut: &LutTable, rgb: [f32; 3], interp: LutInterpolation) -> [f32; 3] {
+    let n = lut.size;
+    let (xr, xg, xb) = (
+        lut.coord(rgb[0], 0),
+        lut.coord(rgb[1], 1),
+        lut.coord(rgb[2], 2),
+    );
+    let (r0, g0, b0) = (
+        (xr as usize).min(n - 2),
+        (xg as usize).min(n - 2),
+        (xb as usize).min(n - 2),
+    );
+    let (fr, fg, fb) = (xr - r0 as f32, xg - g0 as f32, xb - b0 as f32);
+    let at = |dr: usize, dg: usize, db: usize| lut.data[((b0 + db) * n + g0 + dg) * n + r0 + dr];
+    let mix = |a: [f32; 3], b: [f32; 3], t: f32| {
+        [
+            a[0] + t * (b[0] - a[0]),
+            a[1] + t * (b[1] - a[1]),
+            a[2] + t * (b[2] - a[2]),
+        ]
+    };
+
+    match interp {
+        LutInterpolation::Trilinear => {
+            let c00 = mix(at(0, 0, 0), at(1, 0, 0), fr);
+            let c10 = mix(at(0, 1, 0), at(1, 1, 0), fr);
+            let c01 = mix(at(0, 0, 1), at(1, 0, 1), fr);
+            let c11 = mix(at(0, 1, 1), at(1, 1, 1), fr);
+            mix(mix(c00, c10, fg), mix(c01, c11, fg), fb)
+        }
+        LutInterpolation::Tetrahedral => {
+            // Walk from the (0,0,0) corner to (1,1,1) along the axes in
+            // decreasing order of their fractions; weights are the gaps.
+            let c000 = at(0, 0, 0);
+            let c111 = at(1, 1, 1);
+            let (w0, w1, w2, w3, p1, p2) = if fr >= fg {
+                if fg >= fb {
+                    (1.0 - fr, fr - fg, fg - fb, fb, at(1, 0, 0), at(1, 1, 0))
+                } else if fr >= fb {
+                    (1.0 - fr, fr - fb, fb - fg, fg, at(1, 0, 0), at(1, 0, 1))
+                } else {
+                    (1.0 - fb, fb - fr, fr - fg, fg, at(0, 0, 1), at(1, 0, 1))
+                }
+            } else if fb >= fg {
+                (1.0 - fb, fb - fg, fg - fr, fr, at(0, 0, 1), at(0, 1, 1))
+            } else if fb >= fr {
+                (1.0 - fg, fg - fb, fb - fr, fr, at(0, 1, 0), at(0, 1, 1))
+            } else {
+                (1.0 - fg, fg - fr, fr - fb, fb, at(0, 1, 0), at(1, 1, 0))
+            };
+            let mut out = [0.0f32; 3];
+            for c in 0..3 {
+                out[c] = w0 * c000[c] + w1 * p1[c] + w2 * p2[c] + w3 * c111[c];
+            }
+            out
+        }
+    }
+}
+
+/// Grade an RGBA buffer through a .cube LUT, blended by `strength` (0–1).
+/// Alpha is preserved.
+pub fn apply_cube(rgba: &[u8], lut: &CubeLut, interp: LutInterpolation, strength: f32) -> Vec<u8> {
+    let strength = strength.clamp(0.0, 1.0);
+    let mut out = rgba.to_vec();
+    if strength == 0.0 {
+        return out;
+    }
+
+    // The 1D stage only sees 256 input levels per channel: tabulate it
+    let shaper: Option<Vec<[f32; 3]>> = lut.lut_1d.as_ref().map(|t| {
+        (0..256)
+            .map(|i| {
+                let v = i as f32 / 255.0;
+                sample_1d(t, [v, v, v])
+            })
+            .collect()
+    });
+
+    for px in out.chunks_exact_mut(4) {
+        let mut rgb = match &shaper {
+            Some(s) => [
+                s[px[0] as usize][0],
+                s[px[1] as usize][1],
+                s[px[2] as usize][2],
+            ],
+            None => [
+                px[0] as f32 / 255.0,
+                px[1] as f32 / 255.0,
+                px[2] as f32 / 255.0,
+            ],
+        };
+        if let Some(t) = &lut.lut_3d {
+            rgb = sample_3d(t, rgb, interp);
+        }
+        for c in 0..3 {
+            let orig = px[c] as f32;
+            let graded = rgb[c].clamp(0.0, 1.0) * 255.0;
+            px[c] = (orig + strength * (graded - orig) + 0.5).clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
+
+/// Write a 3D .cube file of `size`³ samples of `transform` (sRGB 0–1 in/out).
+/// `size` is clamped to 2..=`MAX_EXPORT_SIZE`.
+pub fn write_cube(title: &str, size: usize, transform: impl Fn([f32; 3]) -> [f32; 3]) -> String {
+    let size = size.clamp(2, MAX_EXPORT_SIZE);
+    let mut out = String::with_capacity(size * size * size * 28 + 64);
+    if !title.is_empty() {
+        out.push_str(&format!("TITLE \"{}\"\n", title.replace('"', "'")));
+    }
+    out.push_str(&format!("LUT_3D_SIZE {size}\n"));
+    out.push_str("DOMAIN_MIN 0.0 0.0 0.0\nDOMAIN_MAX 1.0 1.0 1.0\n");
+    let scale = 1.0 / (size - 1) as f32;
+    // Red varies fastest, then green, then blue
+    for b in 0..size {
+        for g in 0..size {
+            for r in 0..size {
+                let v = transform([r as f32 * scale, g as f32 * scale, b as f32 * scale]);
+                out.push_str(&format!("{:.6} {:.6} {:.6}\n", v[0], v[1], v[2]));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity_cube(size: usize) -> CubeLut {
+        parse_cube(&write_cube("identity", size, |c| c)).unwrap()
+    }
+
+    #[test]
+    fn test_cube_parse_header_and_rows() {
+        let text = "# comment\nTITLE \"Warm\"\nLUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 1 1 1\n\
+                    0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+        let lut = parse_cube(text).unwrap();
+        assert_eq!(lut.title, "Warm");
+        assert!(lut.lut_1d.is_none());
+        let t = lut.lut_3d.unwrap();
+        assert_eq!(t.size, 2);
+        assert_eq!(t.data[1], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_cube_rejects_bad_row_count() {
+        let err = parse_cube("LUT_3D_SIZE 2\n0 0 0\n").unwrap_err();
+        assert!(err.contains("expected 8"));
+        assert!(parse_cube("0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn test_cube_identity_both_interpolations() {
+        let lut = identity_cube(17);
+        let rgba: Vec<u8> = (0..=255u8).flat_map(|v| [v, 255 - v, v / 3, v]).collect();
+        for interp in [LutInterpolation::Trilinear, LutInterpolation::Tetrahedral] {
+            let out = apply_cube(&rgba, &lut, interp, 1.0);
+            for (a, b) in out.iter().zip(&rgba) {
+                assert!((*a as i16 - *b as i16).abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cube_size_limits() {
+        let lut = identity_cube(1000);
+        assert_eq!(lut.lut_3d.unwrap().size, MAX_EXPORT_SIZE);
+        let err = parse_cube("LUT_3D_SIZE 130\n").unwrap_err();
+        assert!(err.contains("130"), "{err}");
+    }
+
+    #[test]
+    fn test_cube_1d_invert_and_strength() {
+        let lut = parse_cube("LUT_1D_SIZE 2\n1 1 1\n0 0 0\n").unwrap();
+        let out = apply_cube(&[200, 100, 0, 77], &lut, LutInterpolation::Tetrahedral, 1.0);
+        assert_eq!(out, vec![55, 155, 255, 77]);
+        let half = apply_cube(&[200, 100, 0, 77], &lut, LutInterpolation::Tetrahedral, 0.5);
+        assert_eq!(&half[..3], &[128, 128, 128]);
+    }
+
+    #[test]
+    fn test_cube_tetrahedral_gray_stays_gray() {
+        // Channel-swapping cube: grays map to grays under tetrahedral lookup
+        let lut = parse_cube(&write_cube("", 5, |c| [c[1], c[2], c[0]])).unwrap();
+        let out = apply_cube(&[90, 90, 90, 255], &lut, LutInterpolation::Tetrahedral, 1.0);
+        assert_eq!(&out[..3], &[90, 90, 90]);
+        let out = apply_cube(&[200, 40, 10, 255], &lut, LutInterpolation::Trilinear, 1.0);
+        assert_eq!(&out[..3], &[40, 10, 200]);
+    }
+}
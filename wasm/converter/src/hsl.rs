@@ -0,0 +1,261 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — HSL / Selective Color Mixer
+//
+// Per-hue-band hue, saturation and luminance adjustments in Oklch (the
+// polar form of Oklab: L lightness, C chroma, h hue). Oklab is close to
+// perceptually uniform, so a luminance change keeps the hue and a hue
+// rotation keeps the perceived lightness — unlike HSL/HSV on sRGB, where
+// turning yellow into blue also makes it much darker.
+//
+//   1. Bands: 8 centers at the Oklch hues of the sRGB primaries and
+//      secondaries (red, orange, yellow, green, aqua, blue, purple,
+//      magenta). Between two neighboring centers, weights cross-fade with
+//      a smoothstep, so the weights always sum to 1: a hue halfway between
+//      blue and purple gets half of each band, with no seams.
+//
+//   2. Adjustments, blended by band weight:
+//        h' = h + Δh
+//        C' = C · (1 + saturation)          (−1 = gray)
+//        L' = L · (1 + LUM_GAIN · luminance)
+//      Everything fades out below NEUTRAL_CHROMA: grays have no defined
+//      hue and must not pick up a band's adjustment.
+//
+//   3. Replace color: pixels within `tolerance` (Oklab ΔE × 100, lightness
+//      difference half-weighted so shaded parts of an object still match)
+//      of a source color are rotated by a hue shift. Full effect up to half
+//      the tolerance, smooth falloff to zero at the tolerance.
+//
+// Colors pushed out of the sRGB gamut lose chroma (bisection at fixed L
+// and h) instead of being clipped per channel, which would shift hue.
+// Alpha is passed through unchanged.
+//
+// References:
+//   - Ottosson, B. "A perceptual color space for image processing", 2020
+//     (Oklab; matrices from the reference implementation)
+// ═══════════════════════════════════════════════════════════════════
+
+use imaging::oklab::{fit_chroma, linear_to_oklab, oklab_to_linear};
+use imaging::srgb::{build_srgb_to_linear_lut, linear_to_srgb};
+
+pub const BANDS: usize = 8;
+/// Floats per band: hue shift (degrees), saturation, luminance.
+pub const BAND_STRIDE: usize = 3;
+
+/// Oklch hues (degrees) of sRGB red, orange (#ff8000), yellow, green, aqua,
+/// blue, purple (#8000ff) and magenta. Skin tones sit near orange.
+const BAND_CENTERS: [f32; BANDS] = [29.2, 53.0, 109.8, 142.5, 194.8, 264.1, 293.9, 328.4];
+/// Chroma below which adjustments fade out (neutral colors).
+const NEUTRAL_CHROMA: f32 = 0.03;
+/// Lightness factor range for luminance ±1.
+const LUM_GAIN: f32 = 0.5;
+
+#[inline]
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Linear RGB of Oklch (`l`, `c`, `h` in radians), chroma reduced until
+/// it fits in the sRGB gamut.
+fn oklch_to_linear_fit(l: f32, c: f32, h: f32) -> [f32; 3] {
+    let (sin, cos) = h.sin_cos();
+    fit_chroma([l, c * cos, c * sin], oklab_to_linear)
+}
+
+/// Band weights for hue `deg` (0–360): two neighboring bands, summing to 1.
+fn band_weights(deg: f32) -> [(usize, f32); 2] {
+    let mut i = BANDS - 1;
+    for (k, &c) in BAND_CENTERS.iter().enumerate() {
+        if deg >= c {
+            i = k;
+        }
+    }
+    let j = (i + 1) % BANDS;
+    let start = BAND_CENTERS[i];
+    let mut end = BAND_CENTERS[j];
+    let mut d = deg;
+    if end <= start {
+        end += 360.0;
+    }
+    if d < start {
+        d += 360.0;
+    }
+    let t = smoothstep(0.0, 1.0, (d - start) / (end - start));
+    [(i, 1.0 - t), (j, t)]
+}
+
+/// Hue / saturation / luminance adjustment of one band. Default = none.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BandAdjust {
+    /// Degrees.
+    pub hue: f32,
+    /// −1 (gray) … 1 (double chroma).
+    pub saturation: f32,
+    /// −1 … 1.
+    pub luminance: f32,
+}
+
+/// One `BandAdjust` per band, red … magenta.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HslMixer {
+    pub bands: [BandAdjust; BANDS],
+}
+
+impl HslMixer {
+    /// From `[hue, saturation, luminance]` per band, red first. Missing
+    /// bands are left unchanged.
+    pub fn from_slice(v: &[f32]) -> Self {
+        let mut mixer = HslMixer::default();
+        for (band, chunk) in mixer.bands.iter_mut().zip(v.chunks_exact(BAND_STRIDE)) {
+            *band = BandAdjust {
+                hue: chunk[0],
+                saturation: chunk[1].clamp(-1.0, 1.0),
+                luminance: chunk[2].clamp(-1.0, 1.0),
+            };
+        }
+        mixer
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.bands.iter().all(|b| *b == BandAdjust::default())
+    }
+
+    pub fn apply(&self, rgba: &[u8]) -> Vec<u8> {
+        if self.is_identity() {
+            return rgba.to_vec();
+        }
+        map_oklch(rgba, |l, c, h| {
+            let mut adj = BandAdjust::default();
+            for (band, weight) in band_weights(h.to_degrees().rem_euclid(360.0)) {
+                let b = &self.bands[band];
+                adj.hue += weight * b.hue;
+                adj.saturation += weight * b.saturation;
+                adj.luminance += weight * b.luminance;
+            }
+            let k = smoothstep(0.0, NEUTRAL_CHROMA, c);
+            (
+                (l * (1.0 + LUM_GAIN * adj.luminance * k)).clamp(0.0, 1.0),
+                c * (1.0 + adj.saturation * k).max(0.0),
+                h + (adj.hue * k).to_radians(),
+            )
+        })
+    }
+}
+
+/// Rotate the hue of colors near `source` (sRGB) by `hue_shift` degrees.
+///
+/// `tolerance` is in Oklab ΔE × 100 (≈ 2 is a just-noticeable difference).
+pub fn replace_color(rgba: &[u8], source: [u8; 3], tolerance: f32, hue_shift: f32) -> Vec<u8> {
+    if tolerance <= 0.0 || hue_shift == 0.0 {
+        return rgba.to_vec();
+    }
+    let lut = build_srgb_to_linear_lut();
+    let src = linear_to_oklab(source.map(|v| lut[v as usize]));
+    let radius = tolerance / 100.0;
+    let shift = hue_shift.to_radians();
+
+    map_oklch(rgba, |l, c, h| {
+        let (sin, cos) = h.sin_cos();
+        let dl = 0.5 * (l - src[0]);
+        let da = c * cos - src[1];
+        let db = c * sin - src[2];
+        let distance = (dl * dl + da * da + db * db).sqrt();
+        let weight = 1.0 - smoothstep(0.5 * radius, radius, distance);
+        (l, c, h + weight * shift)
+    })
+}
+
+/// Apply `f(L, C, h)` → (L', C', h') to every pixel (h in radians). Pixels
+/// `f` leaves unchanged are copied exactly, skipping the round trip.
+fn map_oklch(rgba: &[u8], f: impl Fn(f32, f32, f32) -> (f32, f32, f32)) -> Vec<u8> {
+    let lut = build_srgb_to_linear_lut();
+    let mut out = rgba.to_vec();
+    for px in out.chunks_exact_mut(4) {
+        let lab = linear_to_oklab([
+            lut[px[0] as usize],
+            lut[px[1] as usize],
+            lut[px[2] as usize],
+        ]);
+        let c = lab[1].hypot(lab[2]);
+        let h = lab[2].atan2(lab[1]);
+        let (l2, c2, h2) = f(lab[0], c, h);
+        if l2 == lab[0] && c2 == c && h2 == h {
+            continue;
+        }
+        let rgb = oklch_to_linear_fit(l2, c2, h2);
+        px[0] = linear_to_srgb(rgb[0]);
+        px[1] = linear_to_srgb(rgb[1]);
+        px[2] = linear_to_srgb(rgb[2]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hue_of(p: &[u8]) -> f32 {
+        let lut = build_srgb_to_linear_lut();
+        let lab = linear_to_oklab([lut[p[0] as usize], lut[p[1] as usize], lut[p[2] as usize]]);
+        lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0)
+    }
+
+    #[test]
+    fn test_band_weights_partition_of_unity() {
+        for step in 0..720 {
+            let deg = step as f32 * 0.5;
+            let [(_, a), (_, b)] = band_weights(deg);
+            assert!((a + b - 1.0).abs() < 1e-6, "hue {deg}");
+        }
+        let [(i, a), _] = band_weights(BAND_CENTERS[5]);
+        assert_eq!((i, a), (5, 1.0));
+        // Wraps from magenta to red across 0°
+        let [(i, _), (j, _)] = band_weights(5.0);
+        assert_eq!((i, j), (7, 0));
+    }
+
+    #[test]
+    fn test_identity_is_exact() {
+        let rgba: Vec<u8> = (0..=255u8).flat_map(|v| [v, 255 - v, v / 3, v]).collect();
+        assert_eq!(HslMixer::default().apply(&rgba), rgba);
+    }
+
+    #[test]
+    fn test_desaturate_blues_only() {
+        let mut v = [0.0f32; BANDS * BAND_STRIDE];
+        v[5 * BAND_STRIDE + 1] = -1.0;
+        let mixer = HslMixer::from_slice(&v);
+        let rgba = [0, 0, 255, 255, 255, 0, 0, 255, 128, 128, 128, 255];
+        let out = mixer.apply(&rgba);
+
+        let blue = &out[0..3];
+        assert!(blue[0].abs_diff(blue[2]) <= 2, "blue not gray: {blue:?}");
+        assert_eq!(&out[4..8], &rgba[4..8], "red changed");
+        assert_eq!(&out[8..12], &rgba[8..12], "gray changed");
+    }
+
+    #[test]
+    fn test_luminance_keeps_hue() {
+        let mut v = [0.0f32; BANDS * BAND_STRIDE];
+        v[3 * BAND_STRIDE + 2] = -0.6;
+        let rgba = [60, 180, 70, 255];
+        let out = HslMixer::from_slice(&v).apply(&rgba);
+        assert!(out[1] < rgba[1] - 30, "not darker: {out:?}");
+        assert!((hue_of(&out) - hue_of(&rgba)).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_replace_color_hits_only_nearby_colors() {
+        let rgba = [
+            200, 40, 40, 255, //
+            170, 30, 30, 255, // darker shade of the same red
+            40, 60, 200, 255,
+        ];
+        let out = replace_color(&rgba, [200, 40, 40], 20.0, 120.0);
+        for px in [&out[0..4], &out[4..8]] {
+            let shift = (hue_of(px) - hue_of(&rgba[0..4])).rem_euclid(360.0);
+            assert!((shift - 120.0).abs() < 8.0, "shift {shift}");
+        }
+        assert_eq!(&out[8..12], &rgba[8..12]);
+    }
+}
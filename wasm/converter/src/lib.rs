@@ -16,18 +16,34 @@
 //   - PackBits compression: Apple Computer Technical Note TN1023
 //   - Grayscale conversion: ITU-R Recommendation BT.709-6 (06/2015)
 //   - Area-average resampling: optimal box-filter downscaling
+//   - 3D LUTs: Adobe Cube LUT Specification 1.0; tetrahedral interpolation
+//     per Kasson, Nin & Plouffe, J. Electronic Imaging, 1995
+//   - Tone curves: Fritsch & Carlson, monotone piecewise cubic interpolation, 1980
+//   - HSL mixer: Oklab / Oklch, B. Ottosson, 2020
 
+mod adjust;
 mod alpha;
 mod avif;
+mod background;
 mod bmp;
 mod color;
+mod compositing;
+mod cube;
+mod hsl;
 mod ico;
 mod resize;
+mod shadow;
+mod stats;
 mod svg_trace;
 mod tiff;
 mod tiff_decode;
+mod tone;
 
+pub use compositing::{add_shadows, alpha_composite_image};
 pub use tiff_decode::decode_tiff;
+pub use tone::{
+    adjust_image, apply_cube_lut, export_adjustments_cube, hsl_mixer, image_stats, replace_color,
+};
 
 use wasm_bindgen::prelude::*;
 
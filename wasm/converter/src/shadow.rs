@@ -0,0 +1,312 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — Synthetic Shadows for Cutouts
+//
+// Generates shadow layers from the cutout's alpha and composites them
+// beneath the subject, for product shots on plain backgrounds.
+//
+//   1. Drop shadow: the alpha mask shifted by (dx, dy), Gaussian-blurred
+//      and scaled by opacity — the classic "floating" card shadow.
+//
+//   2. Ground (cast) shadow: the silhouette is laid onto the floor plane
+//      through its base line (bottom of the subject) and seen in
+//      perspective. A point at height t above the base falls u = length · t
+//      pixels behind the base (measured at the base's scale; `length` is
+//      1 / tan(light elevation) times the floor's foreshortening), leaning
+//      skew · u sideways. With the horizon V pixels above the base and the
+//      vanishing point above the canvas center cx, the floor homography is
+//        s = V / (V + u)
+//        y' = base − u · s,   x' = cx + (x − cx + skew · u) · s
+//      so the shadow narrows and shortens toward the horizon like a real
+//      floor (s → 1 as V → ∞ gives the parallel projection). A negative
+//      `length` casts the shadow toward the viewer, below the base, where
+//      it widens instead. Blur grows and opacity fades with the height of
+//      the casting point, so the shadow is dark and crisp where the object
+//      touches the ground (contact) and soft far away.
+//
+//   3. The two layers are merged with "over" (same color), then the
+//      subject is composited over them with Porter-Duff in linear light,
+//      like alpha::composite_over. Areas without shadow stay transparent.
+//
+// The canvas is not enlarged: shadows reaching past the border are clipped.
+// ═══════════════════════════════════════════════════════════════════
+
+use imaging::blur::gaussian_blur;
+use imaging::srgb::{build_srgb_to_linear_lut, linear_to_srgb};
+
+/// Ground shadow opacity at the far end, relative to the contact point.
+const FAR_OPACITY: f32 = 0.35;
+/// Blur at the contact point, relative to the far end.
+const CONTACT_BLUR: f32 = 0.25;
+/// Alpha above which a pixel counts as solid subject when finding the base.
+const SOLID_ALPHA: f32 = 0.5;
+
+/// Blurred drop-shadow coverage (0–1) of `alpha` shifted by (dx, dy).
+pub fn drop_shadow_layer(
+    alpha: &[f32],
+    w: usize,
+    h: usize,
+    dx: isize,
+    dy: isize,
+    blur_radius: f32,
+    opacity: f32,
+) -> Vec<f32> {
+    let mut shifted = vec![0.0f32; w * h];
+    for y in 0..h {
+        let sy = y as isize - dy;
+        if sy < 0 || sy >= h as isize {
+            continue;
+        }
+        for x in 0..w {
+            let sx = x as isize - dx;
+            if sx < 0 || sx >= w as isize {
+                continue;
+            }
+            shifted[y * w + x] = alpha[sy as usize * w + sx as usize];
+        }
+    }
+
+    let mut layer = gaussian_blur(&shifted, w, h, blur_radius * 0.5);
+    for v in layer.iter_mut() {
+        *v = (*v * opacity).clamp(0.0, 1.0);
+    }
+    layer
+}
+
+/// Ground shadow coverage (0–1), projected onto the floor at the base.
+///
+/// `length` is the shadow length on the floor relative to the subject
+/// height, measured at the base (negative: cast toward the viewer), `skew`
+/// the sideways lean per unit of that length, and `horizon` the height of
+/// the horizon above the base in subject heights (≤ 0: no perspective).
+#[allow(clippy::too_many_arguments)]
+pub fn ground_shadow_layer(
+    alpha: &[f32],
+    w: usize,
+    h: usize,
+    skew: f32,
+    length: f32,
+    horizon: f32,
+    blur_radius: f32,
+    opacity: f32,
+) -> Vec<f32> {
+    let mut layer = vec![0.0f32; w * h];
+
+    // Subject extent: base = lowest solid row, top = highest
+    let solid_row = |y: usize| alpha[y * w..(y + 1) * w].iter().any(|&a| a > SOLID_ALPHA);
+    let (Some(top), Some(base)) = (
+        (0..h).find(|&y| solid_row(y)),
+        (0..h).rfind(|&y| solid_row(y)),
+    ) else {
+        return layer;
+    };
+    if length == 0.0 {
+        return layer;
+    }
+
+    let base_f = base as f32;
+    let height = ((base - top) as f32).max(1.0);
+    let v = if horizon > 0.0 {
+        horizon * height
+    } else {
+        f32::INFINITY
+    };
+    let cx = (w as f32 - 1.0) * 0.5;
+
+    // Inverse mapping: every floor pixel at Δ = base − y' above the base
+    // looks up the silhouette point casting onto it:
+    //   u = Δ · V / (V − Δ),   s = (V − Δ) / V,   t = u / length
+    let mut projected = vec![0.0f32; w * h];
+    let mut casting_height = vec![0.0f32; h];
+    let rows: Vec<usize> = if length > 0.0 {
+        (0..=base).collect()
+    } else {
+        (base..h).collect()
+    };
+    for y in rows {
+        let delta = base_f - y as f32;
+        if delta >= v {
+            continue; // at or above the horizon
+        }
+        let (u, scale) = if v.is_finite() {
+            (delta * v / (v - delta), (v - delta) / v)
+        } else {
+            (delta, 1.0)
+        };
+        let t = u / length;
+        let sy = base_f - t;
+        if sy < 0.0 {
+            continue;
+        }
+        casting_height[y] = (t / height).clamp(0.0, 1.0);
+        for x in 0..w {
+            let sx = (x as f32 - cx) / scale + cx - skew * u;
+            projected[y * w + x] = sample_bilinear(alpha, w, h, sx, sy);
+        }
+    }
+
+    // Contact hardening: crisp near the base, soft far away
+    let sharp = gaussian_blur(&projected, w, h, blur_radius * CONTACT_BLUR * 0.5);
+    let soft = gaussian_blur(&projected, w, h, blur_radius * 0.5);
+    for (y, &d) in casting_height.iter().enumerate() {
+        let fade = opacity * (1.0 - (1.0 - FAR_OPACITY) * d);
+        for x in 0..w {
+            let i = y * w + x;
+            layer[i] = ((sharp[i] * (1.0 - d) + soft[i] * d) * fade).clamp(0.0, 1.0);
+        }
+    }
+
+    layer
+}
+
+/// Composite the subject over the shadow coverage `shadow` tinted `color`.
+pub fn composite_shadow(rgba: &[u8], shadow: &[f32], color: [u8; 3]) -> Vec<u8> {
+    let lut = build_srgb_to_linear_lut();
+    let shadow_lin = [
+        lut[color[0] as usize],
+        lut[color[1] as usize],
+        lut[color[2] as usize],
+    ];
+
+    let mut out = rgba.to_vec();
+    for (i, &s) in shadow.iter().enumerate() {
+        let off = i * 4;
+        let a = rgba[off + 3];
+        if a == 255 || s <= 0.0 {
+            continue;
+        }
+
+        let af = a as f32 / 255.0;
+        let sb = s * (1.0 - af);
+        let ao = af + sb;
+        let inv = 1.0 / ao;
+        for c in 0..3 {
+            let fg = lut[rgba[off + c] as usize];
+            out[off + c] = linear_to_srgb((fg * af + shadow_lin[c] * sb) * inv);
+        }
+        out[off + 3] = (ao * 255.0 + 0.5).min(255.0) as u8;
+    }
+    out
+}
+
+/// Merge two same-colored shadow layers: s = 1 − (1 − s₁)(1 − s₂).
+pub fn merge_layers(a: &mut [f32], b: &[f32]) {
+    for (x, &y) in a.iter_mut().zip(b) {
+        *x = 1.0 - (1.0 - *x) * (1.0 - y);
+    }
+}
+
+fn sample_bilinear(data: &[f32], w: usize, h: usize, x: f32, y: f32) -> f32 {
+    if x < 0.0 || y < 0.0 || x > (w - 1) as f32 || y > (h - 1) as f32 {
+        return 0.0;
+    }
+    let x0 = x as usize;
+    let y0 = y as usize;
+    let x1 = (x0 + 1).min(w - 1);
+    let y1 = (y0 + 1).min(h - 1);
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+    (data[y0 * w + x0] * (1.0 - fx) + data[y0 * w + x1] * fx) * (1.0 - fy)
+        + (data[y1 * w + x0] * (1.0 - fx) + data[y1 * w + x1] * fx) * fy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20×20 transparent canvas with an opaque white 6×8 box at (7, 6).
+    fn boxed() -> (Vec<u8>, Vec<f32>) {
+        let (w, h) = (20, 20);
+        let mut rgba = vec![0u8; w * h * 4];
+        let mut alpha = vec![0.0f32; w * h];
+        for y in 6..14 {
+            for x in 7..13 {
+                let i = y * w + x;
+                rgba[i * 4..i * 4 + 4].copy_from_slice(&[255, 255, 255, 255]);
+                alpha[i] = 1.0;
+            }
+        }
+        (rgba, alpha)
+    }
+
+    #[test]
+    fn test_drop_shadow_offset() {
+        let (_, alpha) = boxed();
+        let layer = drop_shadow_layer(&alpha, 20, 20, 4, 3, 0.0, 0.5);
+        // Shifted box covers (11..17, 9..17)
+        assert!((layer[12 * 20 + 15] - 0.5).abs() < 1e-6);
+        assert_eq!(layer[12 * 20 + 8], 0.0); // left part of the box: no shadow
+    }
+
+    #[test]
+    fn test_subject_unchanged() {
+        let (rgba, alpha) = boxed();
+        let layer = drop_shadow_layer(&alpha, 20, 20, 2, 2, 4.0, 0.8);
+        let out = composite_shadow(&rgba, &layer, [0, 0, 0]);
+        for y in 6..14 {
+            for x in 7..13 {
+                let i = (y * 20 + x) * 4;
+                assert_eq!(&out[i..i + 4], &[255, 255, 255, 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_opacity_is_identity() {
+        let (rgba, alpha) = boxed();
+        let layer = drop_shadow_layer(&alpha, 20, 20, 3, 3, 2.0, 0.0);
+        assert_eq!(composite_shadow(&rgba, &layer, [0, 0, 0]), rgba);
+    }
+
+    #[test]
+    fn test_ground_shadow_rises_from_base() {
+        let (_, alpha) = boxed();
+        let layer = ground_shadow_layer(&alpha, 20, 20, 1.0, 0.5, 0.0, 0.0, 1.0);
+        // Base row 13, subject height 7 → shadow spans rows ~9.5..13, leaning right
+        assert!(layer[13 * 20 + 10] > 0.9);
+        assert!(layer[11 * 20 + 12] > 0.0);
+        assert_eq!(layer[5 * 20 + 10], 0.0);
+        assert_eq!(layer[17 * 20 + 10], 0.0);
+    }
+
+    #[test]
+    fn test_ground_shadow_narrows_with_distance() {
+        // 10-wide post standing on row 29, horizon 1.5 post heights above it
+        let (w, h) = (40, 40);
+        let mut alpha = vec![0.0f32; w * h];
+        for y in 10..30 {
+            for x in 15..25 {
+                alpha[y * w + x] = 1.0;
+            }
+        }
+        let width_at = |layer: &[f32], y: usize| {
+            layer[y * w..(y + 1) * w]
+                .iter()
+                .filter(|&&v| v > 0.25)
+                .count()
+        };
+
+        let behind = ground_shadow_layer(&alpha, w, h, 0.0, 1.0, 1.5, 0.0, 1.0);
+        let (near, far) = (width_at(&behind, 28), width_at(&behind, 20));
+        assert_eq!(near, 10);
+        assert!(far < near && far >= 6, "near {near}, far {far}");
+        // Foreshortened: 19 px of post cast 1:1 reach only ~11 rows up
+        assert!(width_at(&behind, 19) > 0);
+        assert_eq!(width_at(&behind, 16), 0);
+        assert_eq!(width_at(&behind, 35), 0);
+
+        // Cast toward the viewer: below the base, widening
+        let front = ground_shadow_layer(&alpha, w, h, 0.0, -1.0, 1.5, 0.0, 1.0);
+        assert!(width_at(&front, 36) > width_at(&front, 30));
+        assert_eq!(width_at(&front, 20), 0);
+    }
+
+    #[test]
+    fn test_shadow_is_semi_transparent_black() {
+        let (rgba, alpha) = boxed();
+        let layer = drop_shadow_layer(&alpha, 20, 20, 4, 4, 0.0, 0.5);
+        let out = composite_shadow(&rgba, &layer, [0, 0, 0]);
+        let i = (16 * 20 + 14) * 4;
+        assert_eq!(&out[i..i + 3], &[0, 0, 0]);
+        assert_eq!(out[i + 3], 128);
+    }
+}
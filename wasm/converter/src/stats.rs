@@ -0,0 +1,199 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — Histogram & Image Statistics
+//
+// One pass over the RGBA buffer fills everything at once:
+//
+//   1. Histograms (256 bins) of R, G, B, A and luma. Luma uses the same
+//      fixed-point BT.709 weights as to_grayscale (color.rs):
+//        Y = (54×R + 183×G + 19×B + 128) >> 8
+//
+//   2. Distinct colors: a 2²⁴-bit set (2 MiB) indexed by RGB — O(1) per
+//      pixel, no hashing, bounded memory whatever the image size.
+//
+//   3. Grayscale test: the largest channel spread max(R,G,B) − min(R,G,B)
+//      seen. Within GRAY_TOLERANCE levels the image is treated as gray,
+//      so JPEG chroma noise on a gray scan still counts.
+//
+// Min / max / mean / standard deviation are then read off the histograms
+// (exact, 256 steps per channel instead of a second pixel pass).
+//
+// Fully transparent pixels carry no visible color, so R, G, B, luma, the
+// color count and the grayscale test only look at pixels with alpha > 0.
+// The alpha histogram and the transparency fractions cover every pixel.
+// ═══════════════════════════════════════════════════════════════════
+
+/// Largest channel spread (levels) still considered gray.
+const GRAY_TOLERANCE: u8 = 2;
+
+/// Channel order of `ImageStats::histograms` and `ImageStats::channels`.
+pub const CHANNELS: usize = 5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelStats {
+    pub min: u8,
+    pub max: u8,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl ChannelStats {
+    fn from_histogram(hist: &[u32; 256]) -> Self {
+        let n: u64 = hist.iter().map(|&c| c as u64).sum();
+        if n == 0 {
+            return ChannelStats::default();
+        }
+        let min = hist.iter().position(|&c| c > 0).unwrap_or(0) as u8;
+        let max = hist.iter().rposition(|&c| c > 0).unwrap_or(0) as u8;
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        for (v, &c) in hist.iter().enumerate() {
+            let v = v as f64;
+            sum += v * c as f64;
+            sum_sq += v * v * c as f64;
+        }
+        let mean = sum / n as f64;
+        let variance = (sum_sq / n as f64 - mean * mean).max(0.0);
+        ChannelStats {
+            min,
+            max,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+pub struct ImageStats {
+    /// R, G, B, A, luma.
+    pub histograms: [[u32; 256]; CHANNELS],
+    /// R, G, B, A, luma.
+    pub channels: [ChannelStats; CHANNELS],
+    /// Distinct RGB colors among visible pixels.
+    pub unique_colors: u32,
+    /// Fraction of pixels with alpha = 0.
+    pub transparent: f64,
+    /// Fraction of pixels with 0 < alpha < 255.
+    pub semi_transparent: f64,
+    /// Every visible pixel has R ≈ G ≈ B (within `GRAY_TOLERANCE`).
+    pub grayscale: bool,
+}
+
+/// Compute all statistics of an RGBA buffer in a single pass.
+pub fn image_stats(rgba: &[u8]) -> ImageStats {
+    let mut histograms = [[0u32; 256]; CHANNELS];
+    let mut seen = vec![0u64; (1 << 24) / 64];
+    let mut unique_colors = 0u32;
+    let mut transparent = 0usize;
+    let mut semi_transparent = 0usize;
+    let mut max_spread = 0u8;
+
+    for p in rgba.chunks_exact(4) {
+        let (r, g, b, a) = (p[0], p[1], p[2], p[3]);
+        histograms[3][a as usize] += 1;
+        if a == 0 {
+            transparent += 1;
+            continue;
+        }
+        if a < 255 {
+            semi_transparent += 1;
+        }
+
+        histograms[0][r as usize] += 1;
+        histograms[1][g as usize] += 1;
+        histograms[2][b as usize] += 1;
+        let y = (54 * r as u32 + 183 * g as u32 + 19 * b as u32 + 128) >> 8;
+        histograms[4][y as usize] += 1;
+
+        let key = ((r as usize) << 16) | ((g as usize) << 8) | b as usize;
+        let bit = 1u64 << (key & 63);
+        let word = &mut seen[key >> 6];
+        if *word & bit == 0 {
+            *word |= bit;
+            unique_colors += 1;
+        }
+
+        let spread = r.max(g).max(b) - r.min(g).min(b);
+        max_spread = max_spread.max(spread);
+    }
+
+    let total = (rgba.len() / 4).max(1) as f64;
+    let visible = rgba.len() / 4 - transparent;
+    ImageStats {
+        channels: histograms.map(|h| ChannelStats::from_histogram(&h)),
+        histograms,
+        unique_colors,
+        transparent: transparent as f64 / total,
+        semi_transparent: semi_transparent as f64 / total,
+        grayscale: visible > 0 && max_spread <= GRAY_TOLERANCE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histograms_count_visible_pixels() {
+        let rgba = [
+            10, 20, 30, 255, //
+            10, 20, 30, 255, //
+            200, 100, 0, 128, //
+            99, 99, 99, 0,
+        ];
+        let s = image_stats(&rgba);
+        assert_eq!(s.histograms[0][10], 2);
+        assert_eq!(s.histograms[0][200], 1);
+        assert_eq!(s.histograms[0][99], 0, "transparent pixel counted");
+        assert_eq!(s.histograms[3][0], 1);
+        assert_eq!(s.histograms[3].iter().sum::<u32>(), 4);
+        assert_eq!(s.histograms[4].iter().sum::<u32>(), 3);
+        assert_eq!(s.unique_colors, 2);
+        assert!((s.transparent - 0.25).abs() < 1e-12);
+        assert!((s.semi_transparent - 0.25).abs() < 1e-12);
+        assert!(!s.grayscale);
+    }
+
+    #[test]
+    fn test_channel_stats_match_direct_computation() {
+        let rgba: Vec<u8> = (0..1000u32)
+            .flat_map(|i| [(i * 7 % 256) as u8, (i % 97) as u8, 255, 255])
+            .collect();
+        let s = image_stats(&rgba);
+
+        let reds: Vec<f64> = rgba.chunks_exact(4).map(|p| p[0] as f64).collect();
+        let mean = reds.iter().sum::<f64>() / reds.len() as f64;
+        let var = reds.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / reds.len() as f64;
+        assert!((s.channels[0].mean - mean).abs() < 1e-9);
+        assert!((s.channels[0].stddev - var.sqrt()).abs() < 1e-6);
+        assert_eq!((s.channels[1].min, s.channels[1].max), (0, 96));
+        assert_eq!(
+            s.channels[2],
+            ChannelStats {
+                min: 255,
+                max: 255,
+                mean: 255.0,
+                stddev: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_grayscale_tolerates_small_spread() {
+        let gray: Vec<u8> = (0..=255u8)
+            .flat_map(|v| [v, v.saturating_add(1), v, 255])
+            .collect();
+        assert!(image_stats(&gray).grayscale);
+        assert_eq!(image_stats(&gray).unique_colors, 256);
+
+        let tinted = [100, 100, 110, 255];
+        assert!(!image_stats(&tinted).grayscale);
+    }
+
+    #[test]
+    fn test_fully_transparent_image() {
+        let s = image_stats(&[50, 60, 70, 0, 1, 2, 3, 0]);
+        assert_eq!(s.unique_colors, 0);
+        assert_eq!(s.transparent, 1.0);
+        assert!(!s.grayscale);
+        assert_eq!(s.channels[0], ChannelStats::default());
+    }
+}
@@ -0,0 +1,235 @@
+// ═══════════════════════════════════════════════════════════════════
+// PicEdit — Tone & Color Exports
+//
+// wasm-bindgen wrappers for image statistics (stats.rs), fused tone and
+// color adjustments (adjust.rs), .cube LUTs (cube.rs) and the Oklch HSL
+// mixer (hsl.rs).
+// ═══════════════════════════════════════════════════════════════════
+
+use crate::{adjust, cube, hsl, stats};
+use wasm_bindgen::prelude::*;
+
+// ─── Image Statistics ───────────────────────────────────────────────────────
+
+/// Histograms and summary statistics, computed in a single pass.
+///
+/// Returns 1304 values (counts are exact in f64), or an empty Vec on
+/// invalid input:
+/// - `[0..1280)`: 256-bin histograms of R, G, B, A and luma (BT.709)
+/// - `[1280..1300)`: `[min, max, mean, stddev]` of R, G, B, A and luma
+/// - `[1300]`: distinct RGB colors
+/// - `[1301]`: fraction of fully transparent pixels (alpha = 0)
+/// - `[1302]`: fraction of semi-transparent pixels (0 < alpha < 255)
+/// - `[1303]`: 1 if the image is effectively grayscale, else 0
+///
+/// Everything except alpha ignores fully transparent pixels.
+#[wasm_bindgen]
+pub fn image_stats(rgba: &[u8], width: u32, height: u32) -> Vec<f64> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return Vec::new();
+    }
+
+    let s = stats::image_stats(rgba);
+    let mut out = Vec::with_capacity(stats::CHANNELS * 260 + 4);
+    for hist in &s.histograms {
+        out.extend(hist.iter().map(|&c| c as f64));
+    }
+    for c in &s.channels {
+        out.extend_from_slice(&[c.min as f64, c.max as f64, c.mean, c.stddev]);
+    }
+    out.extend_from_slice(&[
+        s.unique_colors as f64,
+        s.transparent,
+        s.semi_transparent,
+        if s.grayscale { 1.0 } else { 0.0 },
+    ]);
+    out
+}
+
+// ─── Adjustments ────────────────────────────────────────────────────────────
+
+/// Apply tone and color adjustments in one fused pass.
+///
+/// - `exposure`: stops (+1 doubles linear light)
+/// - `contrast`: −1 (flat) … 1 (S-curve around mid-gray)
+/// - `levels`: up to 4 groups (master, R, G, B) of
+///   `[in_black, in_white, gamma, out_black, out_white]`, 0–255 units;
+///   missing groups are identity
+/// - `curves`: up to 4 groups (master, R, G, B) of `[n, x0, y0, …, xn, yn]`
+///   control points, 0–255 units; `n` < 2 means no curve
+/// - `saturation`, `vibrance`: −1 … 1 (vibrance favors muted colors)
+/// - `temperature`: −1 (cool) … 1 (warm); `tint`: −1 (green) … 1 (magenta)
+///
+/// Alpha is preserved. Returns the input unchanged on invalid dimensions.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn adjust_image(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    exposure: f32,
+    contrast: f32,
+    levels: &[f32],
+    curves: &[f32],
+    saturation: f32,
+    vibrance: f32,
+    temperature: f32,
+    tint: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+
+    let adj = adjustments_from_args(
+        exposure,
+        contrast,
+        levels,
+        curves,
+        saturation,
+        vibrance,
+        temperature,
+        tint,
+    );
+    adj.apply(rgba)
+}
+
+/// Export adjustment settings (same arguments as `adjust_image`) as a 3D
+/// `.cube` LUT with `size`³ samples (17, 33 or 65 are common; larger sizes
+/// are clamped to 65), so the look can be reused in other editors. Exposure
+/// and white balance clip at 1.0, as the .cube domain is 0–1.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn export_adjustments_cube(
+    title: &str,
+    size: u32,
+    exposure: f32,
+    contrast: f32,
+    levels: &[f32],
+    curves: &[f32],
+    saturation: f32,
+    vibrance: f32,
+    temperature: f32,
+    tint: f32,
+) -> String {
+    let adj = adjustments_from_args(
+        exposure,
+        contrast,
+        levels,
+        curves,
+        saturation,
+        vibrance,
+        temperature,
+        tint,
+    );
+    cube::write_cube(title, size as usize, |rgb| adj.apply_rgb(rgb))
+}
+
+/// Build an `Adjustments` from the flat wasm arguments of `adjust_image`.
+#[allow(clippy::too_many_arguments)]
+fn adjustments_from_args(
+    exposure: f32,
+    contrast: f32,
+    levels: &[f32],
+    curves: &[f32],
+    saturation: f32,
+    vibrance: f32,
+    temperature: f32,
+    tint: f32,
+) -> adjust::Adjustments {
+    let mut level_set = [adjust::Levels::IDENTITY; 4];
+    for (slot, group) in level_set
+        .iter_mut()
+        .zip(levels.chunks_exact(adjust::LEVELS_STRIDE))
+    {
+        *slot = adjust::Levels::from_slice(group);
+    }
+    adjust::Adjustments {
+        exposure,
+        contrast,
+        levels: level_set,
+        curves: adjust::Curve::parse_set(curves),
+        saturation,
+        vibrance,
+        temperature,
+        tint,
+    }
+}
+
+/// Grade RGBA pixels through a `.cube` LUT (1D, 3D, or a 1D shaper + 3D).
+///
+/// - `cube`: the .cube file text
+/// - `interpolation`: "tetrahedral" (default) or "trilinear"
+/// - `strength`: 0 (original) … 1 (full grade)
+///
+/// Alpha is preserved. Errors on malformed .cube text or invalid dimensions.
+#[wasm_bindgen]
+pub fn apply_cube_lut(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    cube: &str,
+    interpolation: &str,
+    strength: f32,
+) -> Result<Vec<u8>, JsError> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return Err(JsError::new("RGBA length does not match width × height"));
+    }
+    let lut = cube::parse_cube(cube).map_err(|e| JsError::new(&e))?;
+    Ok(cube::apply_cube(
+        rgba,
+        &lut,
+        cube::LutInterpolation::from_name(interpolation),
+        strength,
+    ))
+}
+
+/// Per-hue-band hue / saturation / luminance mixer in Oklch.
+///
+/// `bands` holds up to 8 groups of `[hue, saturation, luminance]` for red,
+/// orange, yellow, green, aqua, blue, purple and magenta (missing groups are
+/// unchanged): hue in degrees, saturation and luminance −1 … 1. Neighboring
+/// bands overlap smoothly and neutral grays are left alone. Returns the
+/// input copy on invalid dimensions.
+#[wasm_bindgen]
+pub fn hsl_mixer(rgba: &[u8], width: u32, height: u32, bands: &[f32]) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+    hsl::HslMixer::from_slice(bands).apply(rgba)
+}
+
+/// Replace color: rotate the hue of colors near the source color.
+///
+/// - `source_r/g/b`: the color to change (e.g. picked from the image)
+/// - `tolerance`: match radius in Oklab ΔE × 100 (10–30 typical); matches
+///   fade out smoothly toward it
+/// - `hue_shift`: degrees
+///
+/// Returns the input copy on invalid dimensions.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn replace_color(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    source_r: u8,
+    source_g: u8,
+    source_b: u8,
+    tolerance: f32,
+    hue_shift: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+    hsl::replace_color(rgba, [source_r, source_g, source_b], tolerance, hue_shift)
+}
@@ -0,0 +1,16 @@
+// PicEdit — Shared Imaging Primitives
+//
+// Plain Rust library linked into the WASM crates, so an algorithm that more
+// than one of them needs lives in exactly one place. No wasm-bindgen exports
+// here: each crate keeps its own thin #[wasm_bindgen] wrappers.
+//
+// Modules:
+// - blur: box and Gaussian blurs of float planes (converter, pre-refinement sharpen)
+// - nlm: non-local means denoising (pre-refinement, compressor)
+// - oklab: Oklab conversions and gamut fitting (pre-refinement, converter)
+// - srgb: sRGB ↔ linear transfer (pre-refinement, post-refinement, converter)
+
+pub mod blur;
+pub mod nlm;
+pub mod oklab;
+pub mod srgb;
@@ -0,0 +1,168 @@
+/// Non-Local Means denoising (Buades, Coll & Morel, 2005) — O(w*h*S²),
+/// independent of the patch size.
+///
+/// Every pixel becomes a weighted average of the pixels in its search window,
+/// weighted by how similar their surrounding patches are:
+///   w(p, q) = exp(−d(p, q) / h²),   d = mean squared RGB patch difference
+/// Bilateral filters compare single pixels, so they blur fine texture before
+/// the noise is gone; NLM compares whole patches and keeps repeated texture.
+///
+/// Fast evaluation (Darbon et al., 2008): for each shift s in the search
+/// window, the per-pixel squared difference D(p) = |I(p) − I(p + s)|² is
+/// summed into an integral image, so every patch distance is an O(1) box sum.
+///
+/// Optimizations:
+/// - Symmetric shifts: w(p, p + s) = w(p + s, p), so only half of the search
+///   window is visited and each weight updates both pixels
+/// - Border pixels replicate the edge, so every box sum covers valid data
+/// - The center pixel gets the largest weight seen among its neighbors
+pub fn nlm_denoise(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    search_radius: usize,
+    patch_radius: usize,
+    strength: f32,
+) {
+    if search_radius == 0 || strength <= 0.0 {
+        return;
+    }
+    let npx = w * h;
+    let s = search_radius as isize;
+    let pr = patch_radius as isize;
+    let inv_h2 = 1.0 / (strength * strength);
+
+    let src: Vec<[f32; 3]> = rgba
+        .chunks_exact(4)
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    let mut acc = vec![[0.0f32; 3]; npx];
+    let mut wsum = vec![0.0f32; npx];
+    let mut wmax = vec![0.0f32; npx];
+
+    let iw = w + 1;
+    let mut integral = vec![0.0f64; iw * (h + 1)];
+
+    for dy in 0..=s {
+        for dx in -s..=s {
+            // Half window: the mirrored shift is covered by symmetry
+            if dy == 0 && dx <= 0 {
+                continue;
+            }
+
+            // Integral image of D(p) with replicated borders
+            for y in 0..h {
+                let qy = (y as isize + dy).min(h as isize - 1) as usize;
+                let mut row = 0.0f64;
+                for x in 0..w {
+                    let qx = (x as isize + dx).clamp(0, w as isize - 1) as usize;
+                    let a = src[y * w + x];
+                    let b = src[qy * w + qx];
+                    let d = (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2);
+                    row += d as f64;
+                    integral[(y + 1) * iw + x + 1] = integral[y * iw + x + 1] + row;
+                }
+            }
+
+            for y in 0..h {
+                let qy = y as isize + dy;
+                if qy >= h as isize {
+                    break;
+                }
+                let y0 = (y as isize - pr).max(0) as usize;
+                let y1 = (y as isize + pr + 1).min(h as isize) as usize;
+                for x in 0..w {
+                    let qx = x as isize + dx;
+                    if qx < 0 || qx >= w as isize {
+                        continue;
+                    }
+                    let x0 = (x as isize - pr).max(0) as usize;
+                    let x1 = (x as isize + pr + 1).min(w as isize) as usize;
+                    let sum = integral[y1 * iw + x1] + integral[y0 * iw + x0]
+                        - integral[y0 * iw + x1]
+                        - integral[y1 * iw + x0];
+                    let count = ((y1 - y0) * (x1 - x0) * 3) as f64;
+                    let d = (sum / count) as f32;
+                    let wt = (-d * inv_h2).exp();
+
+                    let p = y * w + x;
+                    let q = qy as usize * w + qx as usize;
+                    for c in 0..3 {
+                        acc[p][c] += wt * src[q][c];
+                        acc[q][c] += wt * src[p][c];
+                    }
+                    wsum[p] += wt;
+                    wsum[q] += wt;
+                    wmax[p] = wmax[p].max(wt);
+                    wmax[q] = wmax[q].max(wt);
+                }
+            }
+        }
+    }
+
+    for (i, px) in rgba.chunks_exact_mut(4).enumerate() {
+        let center = if wmax[i] > 0.0 { wmax[i] } else { 1.0 };
+        let inv = 1.0 / (wsum[i] + center);
+        for c in 0..3 {
+            px[c] = ((acc[i][c] + center * src[i][c]) * inv + 0.5).min(255.0) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray `w × h` image from a per-pixel value.
+    fn gray(w: usize, h: usize, mut v: impl FnMut(usize, usize) -> u8) -> Vec<u8> {
+        (0..w * h)
+            .flat_map(|i| {
+                let g = v(i % w, i / w);
+                [g, g, g, 255]
+            })
+            .collect()
+    }
+
+    /// Mean squared difference of the red channels.
+    fn mse(a: &[u8], b: &[u8]) -> f32 {
+        let n = (a.len() / 4) as f32;
+        a.chunks_exact(4)
+            .zip(b.chunks_exact(4))
+            .map(|(p, q)| (p[0] as f32 - q[0] as f32).powi(2))
+            .sum::<f32>()
+            / n
+    }
+
+    #[test]
+    fn test_flat_image_unchanged() {
+        let rgba = gray(12, 12, |_, _| 77);
+        let mut buf = rgba.clone();
+        nlm_denoise(&mut buf, 12, 12, 3, 1, 10.0);
+        assert_eq!(buf, rgba);
+    }
+
+    #[test]
+    fn test_noise_on_ramp_is_reduced() {
+        let ramp = |x: usize, _: usize| (60 + 4 * x) as u8;
+        let clean = gray(32, 32, ramp);
+        // Deterministic uniform noise in ±12 (σ ≈ 7)
+        let mut state = 99u32;
+        let mut buf = gray(32, 32, |x, y| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (ramp(x, y) as i32 + ((state >> 16) % 25) as i32 - 12) as u8
+        });
+        let before = mse(&buf, &clean);
+        nlm_denoise(&mut buf, 32, 32, 5, 1, 10.0);
+        let after = mse(&buf, &clean);
+        assert!(after < before * 0.5, "{before} → {after}");
+    }
+
+    #[test]
+    fn test_edge_is_kept() {
+        let mut buf = gray(16, 16, |x, _| if x < 8 { 40 } else { 210 });
+        nlm_denoise(&mut buf, 16, 16, 5, 1, 10.0);
+        let row = 8 * 16;
+        assert_eq!(buf[(row + 7) * 4], 40);
+        assert_eq!(buf[(row + 8) * 4], 210);
+    }
+}
@@ -0,0 +1,59 @@
+// Oklab (Björn Ottosson, "A perceptual color space for image processing",
+// 2020) on linear-light sRGB (0–1): perceptually uniform, L in [0, 1].
+// Matrices from the reference implementation.
+//
+// `fit_chroma` maps out-of-gamut colors back by shrinking chroma at fixed
+// lightness and hue, instead of clipping channels (which shifts hue). It
+// works for any opponent space with a lightness axis, CIELAB included.
+
+pub fn linear_to_oklab(c: [f32; 3]) -> [f32; 3] {
+    let l = 0.412_221_46 * c[0] + 0.536_332_55 * c[1] + 0.051_445_995 * c[2];
+    let m = 0.211_903_5 * c[0] + 0.680_699_5 * c[1] + 0.107_396_96 * c[2];
+    let s = 0.088_302_46 * c[0] + 0.281_718_85 * c[1] + 0.629_978_7 * c[2];
+    let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+pub fn oklab_to_linear(lab: [f32; 3]) -> [f32; 3] {
+    let l = lab[0] + 0.396_337_78 * lab[1] + 0.215_803_76 * lab[2];
+    let m = lab[0] - 0.105_561_346 * lab[1] - 0.063_854_17 * lab[2];
+    let s = lab[0] - 0.089_484_18 * lab[1] - 1.291_485_5 * lab[2];
+    let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+}
+
+const GAMUT_STEPS: usize = 12;
+
+/// Linear RGB for `lab` (via `to_linear`), with chroma scaled down by
+/// bisection until every channel lies in [0, 1]. Lightness and hue are kept.
+pub fn fit_chroma(lab: [f32; 3], to_linear: fn([f32; 3]) -> [f32; 3]) -> [f32; 3] {
+    let in_gamut = |c: [f32; 3]| c.iter().all(|&v| (-1e-4..=1.0 + 1e-4).contains(&v));
+    let rgb = to_linear(lab);
+    if in_gamut(rgb) {
+        return rgb;
+    }
+    let mut lo = 0.0f32;
+    let mut hi = 1.0f32;
+    for _ in 0..GAMUT_STEPS {
+        let mid = 0.5 * (lo + hi);
+        if in_gamut(to_linear([lab[0], lab[1] * mid, lab[2] * mid])) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let rgb = to_linear([lab[0], lab[1] * lo, lab[2] * lo]);
+    [
+        rgb[0].clamp(0.0, 1.0),
+        rgb[1].clamp(0.0, 1.0),
+        rgb[2].clamp(0.0, 1.0),
+    ]
+}
@@ -0,0 +1,140 @@
+// sRGB ↔ linear light transfer (IEC 61966-2-1), shared by every crate that
+// blends, blurs or grades in linear light. Decoding goes through a 256-entry
+// LUT; encoding evaluates the inverse curve with a libm-free `pow_f32`.
+
+/// Build the sRGB → linear lookup table (256 entries).
+///
+/// Transfer function (IEC 61966-2-1):
+///   if C_srgb ≤ 0.04045 → C_linear = C_srgb / 12.92
+///   else                → C_linear = ((C_srgb + 0.055) / 1.055)^2.4
+pub fn build_srgb_to_linear_lut() -> [f32; 256] {
+    let mut lut = [0.0f32; 256];
+    let mut i = 0;
+    while i < 256 {
+        lut[i] = srgb_to_linear_f32(i as f32 / 255.0);
+        i += 1;
+    }
+    lut
+}
+
+/// sRGB → linear for a single unquantized channel in [0, 1].
+#[inline]
+pub fn srgb_to_linear_f32(s: f32) -> f32 {
+    if s <= 0.04045 {
+        s / 12.92
+    } else {
+        pow_f32((s + 0.055) / 1.055, 2.4)
+    }
+}
+
+/// Linear → sRGB conversion for a single channel.
+///
+/// Inverse transfer function:
+///   if C_linear ≤ 0.0031308 → C_srgb = 12.92 × C_linear
+///   else                    → C_srgb = 1.055 × C_linear^(1/2.4) − 0.055
+#[inline]
+pub fn linear_to_srgb(c: f32) -> u8 {
+    (linear_to_srgb_f32(c) * 255.0 + 0.5).clamp(0.0, 255.0) as u8
+}
+
+/// Linear → sRGB for a single channel, unquantized (0–1 for inputs in 0–1).
+#[inline]
+pub fn linear_to_srgb_f32(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * pow_f32(c, 1.0 / 2.4) - 0.055
+    }
+}
+
+/// Compute `base^exp` without depending on `std::f32::powf` which may pull
+/// in libm. We use the exp-log identity: base^exp = exp2(exp × log2(base)).
+///
+/// For our use case (sRGB gamma ≈ 2.4, values in [0,1]) the precision of
+/// the hardware-friendly exp2/log2 path is more than sufficient (< 0.5 LSB
+/// error after quantization to u8).
+#[inline]
+pub fn pow_f32(base: f32, exp: f32) -> f32 {
+    if base <= 0.0 {
+        return 0.0;
+    }
+    // Use the identity: x^y = 2^(y * log2(x))
+    f32_exp2(exp * f32_log2(base))
+}
+
+/// Fast log2 approximation using IEEE 754 float bit manipulation.
+///
+/// Based on the observation that the exponent field of an IEEE 754 float
+/// is approximately log2 of the value. A quartic polynomial in the mantissa
+/// corrects the remainder to within ~1e-4 absolute error.
+#[inline]
+fn f32_log2(x: f32) -> f32 {
+    if x <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    let bits = x.to_bits() as i32;
+    let exponent = ((bits >> 23) & 0xFF) - 127;
+    let mantissa_bits = (bits & 0x7FFFFF) | 0x3F800000;
+    let m = f32::from_bits(mantissa_bits as u32);
+    // Minimax polynomial for ln(m) over [1, 2), scaled by 1/ln(2)
+    let ln_m = -1.7417939 + m * (2.8212026 + m * (-1.4699568 + m * (0.44717955 - m * 0.05657085)));
+    exponent as f32 + ln_m * std::f32::consts::LOG2_E
+}
+
+/// Fast exp2 approximation using IEEE 754 float bit manipulation.
+///
+/// Splits input into integer and fractional parts, uses bit manipulation
+/// for the integer part and a polynomial for the fractional part.
+/// Accuracy: < 0.1% relative error over [-10, 10].
+#[inline]
+fn f32_exp2(x: f32) -> f32 {
+    if x < -126.0 {
+        return 0.0;
+    }
+    if x > 128.0 {
+        return f32::INFINITY;
+    }
+    let floor = x.floor();
+    let frac = x - floor;
+    let int_part = floor as i32;
+    // Polynomial approximation for 2^frac over [0, 1); the leading 1 is the
+    // implicit mantissa bit, so only the fractional part goes into the bits
+    let frac_pow = frac
+        * (std::f32::consts::LN_2 + frac * (0.2402265 + frac * (0.0554913 + frac * 0.0096695)));
+    let frac_bits = frac_pow * (1u32 << 23) as f32;
+    let bits = (frac_bits as u32).wrapping_add(((int_part + 127) as u32) << 23);
+    f32::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_roundtrip() {
+        let lut = build_srgb_to_linear_lut();
+        for i in 0..=255u8 {
+            let linear = lut[i as usize];
+            let back = linear_to_srgb(linear);
+            assert!(
+                (back as i16 - i as i16).unsigned_abs() <= 1,
+                "sRGB roundtrip failed for {}: got {}",
+                i,
+                back
+            );
+        }
+    }
+
+    #[test]
+    fn test_pow_f32_matches_powf() {
+        // sRGB decode / encode exponents over the whole unit interval;
+        // 1e-3 is a quarter of an 8-bit level
+        for exp in [2.4f32, 1.0 / 2.4] {
+            for i in 0..=10000 {
+                let x = i as f32 / 10000.0;
+                let err = (pow_f32(x, exp) - x.powf(exp)).abs();
+                assert!(err < 1e-3, "pow_f32({x}, {exp}) off by {err}");
+            }
+        }
+    }
+}
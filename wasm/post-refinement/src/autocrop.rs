@@ -0,0 +1,406 @@
+use std::collections::VecDeque;
+
+/// Largest crop or canvas, in pixels (e.g. 8192 × 4096), so absurd padding,
+/// aspect ratios or canvas sizes fail cleanly instead of exhausting memory.
+pub const MAX_CROP_PIXELS: usize = 1 << 25;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Center,
+    Thirds,
+}
+
+impl Placement {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "thirds" => Placement::Thirds,
+            _ => Placement::Center,
+        }
+    }
+}
+
+/// Inclusive-exclusive pixel rectangle; may extend past the image.
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub x0: isize,
+    pub y0: isize,
+    pub x1: isize,
+    pub y1: isize,
+}
+
+impl Rect {
+    pub fn w(&self) -> isize {
+        self.x1 - self.x0
+    }
+    pub fn h(&self) -> isize {
+        self.y1 - self.y0
+    }
+}
+
+/// Bounding box of alpha > `threshold`, ignoring connected components of
+/// fewer than `min_area` pixels.
+///
+/// Components are 8-connected and labeled with a BFS; dropping the small ones
+/// keeps stray mask islands from widening the box. `None` when nothing is
+/// left.
+pub fn subject_bounds(
+    rgba: &[u8],
+    w: usize,
+    h: usize,
+    threshold: u8,
+    min_area: usize,
+) -> Option<Rect> {
+    let npx = w * h;
+    let mut visited: Vec<bool> = (0..npx).map(|i| rgba[i * 4 + 3] <= threshold).collect();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    let mut component: Vec<usize> = Vec::new();
+    let mut bounds: Option<Rect> = None;
+
+    for start in 0..npx {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        component.clear();
+
+        while let Some(idx) = queue.pop_front() {
+            component.push(idx);
+            let x = idx % w;
+            let y = idx / w;
+            for dy in -1isize..=1 {
+                for dx in -1isize..=1 {
+                    let nx = x as isize + dx;
+                    let ny = y as isize + dy;
+                    if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+                        continue;
+                    }
+                    let ni = ny as usize * w + nx as usize;
+                    if !visited[ni] {
+                        visited[ni] = true;
+                        queue.push_back(ni);
+                    }
+                }
+            }
+        }
+
+        if component.len() < min_area {
+            continue;
+        }
+        for &idx in &component {
+            let x = (idx % w) as isize;
+            let y = (idx / w) as isize;
+            let b = bounds.get_or_insert(Rect {
+                x0: x,
+                y0: y,
+                x1: x + 1,
+                y1: y + 1,
+            });
+            b.x0 = b.x0.min(x);
+            b.y0 = b.y0.min(y);
+            b.x1 = b.x1.max(x + 1);
+            b.y1 = b.y1.max(y + 1);
+        }
+    }
+
+    bounds
+}
+
+/// Grow `subject` (already padded) to `aspect` = width / height.
+///
+/// `w` is the image width, used to pick the third line in `Thirds` mode.
+/// `None` when the grown box would exceed `MAX_CROP_PIXELS`.
+pub fn fit_aspect(subject: Rect, aspect: f32, placement: Placement, w: usize) -> Option<Rect> {
+    let sw = subject.w() as f32;
+    let sh = subject.h() as f32;
+    let (cw, ch) = if sw / sh < aspect {
+        ((sh * aspect).round().max(sw), sh)
+    } else {
+        (sw, (sw / aspect).round().max(sh))
+    };
+    if cw * ch > MAX_CROP_PIXELS as f32 {
+        return None;
+    }
+    let cw = cw as isize;
+    let ch = ch as isize;
+
+    let cx = (subject.x0 + subject.x1) as f32 * 0.5;
+    let cy = (subject.y0 + subject.y1) as f32 * 0.5;
+    let (fx, fy) = match placement {
+        Placement::Center => (0.5, 0.5),
+        Placement::Thirds => {
+            let fx = if cx < w as f32 * 0.5 {
+                1.0 / 3.0
+            } else {
+                2.0 / 3.0
+            };
+            (fx, 1.0 / 3.0)
+        }
+    };
+
+    // Place the subject center at (fx, fy) of the crop, then clamp so the
+    // subject box stays fully inside
+    let x0 = ((cx - fx * cw as f32).round() as isize)
+        .min(subject.x0)
+        .max(subject.x1 - cw);
+    let y0 = ((cy - fy * ch as f32).round() as isize)
+        .min(subject.y0)
+        .max(subject.y1 - ch);
+
+    Some(Rect {
+        x0,
+        y0,
+        x1: x0 + cw,
+        y1: y0 + ch,
+    })
+}
+
+/// Copy `rect` out of the image; pixels outside the image are transparent.
+pub fn extract(rgba: &[u8], w: usize, h: usize, rect: Rect) -> Vec<u8> {
+    let cw = rect.w() as usize;
+    let ch = rect.h() as usize;
+    let mut out = vec![0u8; cw * ch * 4];
+
+    let sx0 = rect.x0.max(0);
+    let sx1 = rect.x1.min(w as isize);
+    if sx0 >= sx1 {
+        return out;
+    }
+    let len = (sx1 - sx0) as usize * 4;
+    for cy in 0..ch {
+        let sy = rect.y0 + cy as isize;
+        if sy < 0 || sy >= h as isize {
+            continue;
+        }
+        let src = (sy as usize * w + sx0 as usize) * 4;
+        let dst = (cy * cw + (sx0 - rect.x0) as usize) * 4;
+        out[dst..dst + len].copy_from_slice(&rgba[src..src + len]);
+    }
+    out
+}
+
+/// Scale the crop to fit inside `dw × dh`, centered on a transparent canvas.
+pub fn place_on_canvas(rgba: &[u8], w: usize, h: usize, dw: usize, dh: usize) -> Vec<u8> {
+    let scale = (dw as f32 / w as f32).min(dh as f32 / h as f32);
+    let tw = ((w as f32 * scale).round() as usize).clamp(1, dw);
+    let th = ((h as f32 * scale).round() as usize).clamp(1, dh);
+    let scaled = resample(rgba, w, h, tw, th);
+
+    let mut out = vec![0u8; dw * dh * 4];
+    let ox = (dw - tw) / 2;
+    let oy = (dh - th) / 2;
+    for y in 0..th {
+        let src = y * tw * 4;
+        let dst = ((y + oy) * dw + ox) * 4;
+        out[dst..dst + tw * 4].copy_from_slice(&scaled[src..src + tw * 4]);
+    }
+    out
+}
+
+/// Separable tent-filter resample with premultiplied alpha.
+///
+/// The tent widens with the reduction ratio, so downscaling averages every
+/// source pixel instead of skipping them. converter's `area_average` can't
+/// stand in: it lives in another wasm crate, averages straight alpha (the
+/// hidden color of transparent pixels bleeds into the cutout edge) and is a
+/// box filter, which turns blocky when a small crop is enlarged to fit.
+fn resample(rgba: &[u8], sw: usize, sh: usize, dw: usize, dh: usize) -> Vec<u8> {
+    if sw == dw && sh == dh {
+        return rgba.to_vec();
+    }
+
+    // Premultiplied f32 planes
+    let mut src = vec![0.0f32; sw * sh * 4];
+    for (i, px) in rgba.chunks_exact(4).enumerate() {
+        let a = px[3] as f32 / 255.0;
+        src[i * 4] = px[0] as f32 * a;
+        src[i * 4 + 1] = px[1] as f32 * a;
+        src[i * 4 + 2] = px[2] as f32 * a;
+        src[i * 4 + 3] = px[3] as f32;
+    }
+
+    let wx = tent_weights(sw, dw);
+    let wy = tent_weights(sh, dh);
+
+    // Horizontal
+    let mut tmp = vec![0.0f32; dw * sh * 4];
+    for y in 0..sh {
+        for (dx, taps) in wx.iter().enumerate() {
+            let mut acc = [0.0f32; 4];
+            for &(sx, wt) in taps {
+                let s = (y * sw + sx) * 4;
+                for c in 0..4 {
+                    acc[c] += src[s + c] * wt;
+                }
+            }
+            tmp[(y * dw + dx) * 4..(y * dw + dx) * 4 + 4].copy_from_slice(&acc);
+        }
+    }
+
+    // Vertical + un-premultiply
+    let mut out = vec![0u8; dw * dh * 4];
+    for (dy, taps) in wy.iter().enumerate() {
+        for x in 0..dw {
+            let mut acc = [0.0f32; 4];
+            for &(sy, wt) in taps {
+                let s = (sy * dw + x) * 4;
+                for c in 0..4 {
+                    acc[c] += tmp[s + c] * wt;
+                }
+            }
+            let off = (dy * dw + x) * 4;
+            let a = acc[3].clamp(0.0, 255.0);
+            if a > 0.0 {
+                let inv = 255.0 / a;
+                for c in 0..3 {
+                    out[off + c] = (acc[c] * inv).clamp(0.0, 255.0) as u8;
+                }
+            }
+            out[off + 3] = (a + 0.5) as u8;
+        }
+    }
+
+    out
+}
+
+/// Normalized tent-filter taps (source index, weight) per destination pixel.
+fn tent_weights(src: usize, dst: usize) -> Vec<Vec<(usize, f32)>> {
+    let ratio = src as f32 / dst as f32;
+    let support = ratio.max(1.0);
+    (0..dst)
+        .map(|d| {
+            let center = (d as f32 + 0.5) * ratio - 0.5;
+            let lo = (center - support).floor().max(0.0) as usize;
+            let hi = ((center + support).ceil() as usize).min(src - 1);
+            let mut taps: Vec<(usize, f32)> = (lo..=hi)
+                .map(|s| (s, (1.0 - (s as f32 - center).abs() / support).max(0.0)))
+                .filter(|&(_, wt)| wt > 0.0)
+                .collect();
+            let total: f32 = taps.iter().map(|t| t.1).sum();
+            if total > 0.0 {
+                for t in taps.iter_mut() {
+                    t.1 /= total;
+                }
+            } else {
+                taps = vec![(center.round().clamp(0.0, (src - 1) as f32) as usize, 1.0)];
+            }
+            taps
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Transparent `w × h` image with opaque pixels where `on` is true.
+    fn mask(w: usize, h: usize, on: impl Fn(usize, usize) -> bool) -> Vec<u8> {
+        let mut rgba = vec![0u8; w * h * 4];
+        for y in 0..h {
+            for x in 0..w {
+                if on(x, y) {
+                    rgba[(y * w + x) * 4..(y * w + x) * 4 + 4]
+                        .copy_from_slice(&[200, 100, 50, 255]);
+                }
+            }
+        }
+        rgba
+    }
+
+    fn bounds(r: Rect) -> (isize, isize, isize, isize) {
+        (r.x0, r.y0, r.x1, r.y1)
+    }
+
+    #[test]
+    fn test_specks_are_ignored() {
+        // 6×4 subject plus a 2×2 speck in the far corner
+        let rgba = mask(20, 20, |x, y| {
+            (5..11).contains(&x) && (8..12).contains(&y) || x >= 18 && y >= 18
+        });
+        let b = subject_bounds(&rgba, 20, 20, 0, 5).unwrap();
+        assert_eq!(bounds(b), (5, 8, 11, 12));
+        // Below the speck size every component counts
+        let b = subject_bounds(&rgba, 20, 20, 0, 4).unwrap();
+        assert_eq!(bounds(b), (5, 8, 20, 20));
+    }
+
+    #[test]
+    fn test_empty_mask() {
+        let rgba = mask(8, 8, |_, _| false);
+        assert!(subject_bounds(&rgba, 8, 8, 0, 1).is_none());
+        // Only specks left is the same as no subject
+        let rgba = mask(8, 8, |x, y| x == 3 && y == 3);
+        assert!(subject_bounds(&rgba, 8, 8, 0, 2).is_none());
+    }
+
+    #[test]
+    fn test_fit_aspect_center() {
+        let subject = Rect {
+            x0: 10,
+            y0: 10,
+            x1: 20,
+            y1: 30,
+        };
+        let r = fit_aspect(subject, 1.0, Placement::Center, 100).unwrap();
+        assert_eq!(bounds(r), (5, 10, 25, 30));
+        let r = fit_aspect(subject, 0.25, Placement::Center, 100).unwrap();
+        assert_eq!(bounds(r), (10, 0, 20, 40));
+    }
+
+    #[test]
+    fn test_fit_aspect_thirds() {
+        let subject = Rect {
+            x0: 10,
+            y0: 40,
+            x1: 20,
+            y1: 50,
+        };
+        // Subject left of the image center: on the left third line, and on
+        // the upper horizontal one
+        let r = fit_aspect(subject, 3.0, Placement::Thirds, 100).unwrap();
+        assert_eq!(r.w(), 30);
+        assert_eq!(15 - r.x0, 10);
+        assert_eq!(r.y0, 40);
+        let r = fit_aspect(subject, 1.0 / 3.0, Placement::Thirds, 100).unwrap();
+        assert_eq!(bounds(r), (10, 35, 20, 65));
+        // Right of center: the right third line
+        let subject = Rect {
+            x0: 80,
+            y0: 40,
+            x1: 90,
+            y1: 50,
+        };
+        let r = fit_aspect(subject, 3.0, Placement::Thirds, 100).unwrap();
+        assert_eq!(85 - r.x0, 20);
+    }
+
+    #[test]
+    fn test_fit_aspect_capped() {
+        let subject = Rect {
+            x0: 0,
+            y0: 0,
+            x1: 4000,
+            y1: 4000,
+        };
+        assert!(fit_aspect(subject, 1e6, Placement::Center, 4000).is_none());
+    }
+
+    #[test]
+    fn test_extract_pads_with_transparency() {
+        let rgba = mask(4, 4, |_, _| true);
+        let rect = Rect {
+            x0: -1,
+            y0: -1,
+            x1: 5,
+            y1: 5,
+        };
+        let out = extract(&rgba, 4, 4, rect);
+        assert_eq!(out.len(), 6 * 6 * 4);
+        for y in 0..6 {
+            for x in 0..6 {
+                let inside = (1..5).contains(&x) && (1..5).contains(&y);
+                assert_eq!(out[(y * 6 + x) * 4 + 3] == 255, inside, "({x}, {y})");
+            }
+        }
+    }
+}
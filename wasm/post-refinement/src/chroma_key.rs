@@ -0,0 +1,179 @@
+const BORDER_FRACTION: usize = 50;
+
+/// Per-channel median RGB of a border ring of the image, as the auto-detected
+/// key color.
+///
+/// The screen fills the frame edges in studio shots. The ring is
+/// min(w, h) / BORDER_FRACTION pixels thick, at least 2.
+pub fn detect_key_color(rgba: &[u8], w: usize, h: usize) -> [u8; 3] {
+    let ring = (w.min(h) / BORDER_FRACTION)
+        .max(2)
+        .min(w.min(h).div_ceil(2));
+    let mut hist = [[0u32; 256]; 3];
+    let mut count = 0u32;
+    for y in 0..h {
+        let edge_row = y < ring || y >= h - ring;
+        for x in 0..w {
+            if !edge_row && x >= ring && x < w - ring {
+                continue;
+            }
+            let off = (y * w + x) * 4;
+            for c in 0..3 {
+                hist[c][rgba[off + c] as usize] += 1;
+            }
+            count += 1;
+        }
+    }
+
+    let mut key = [0u8; 3];
+    for c in 0..3 {
+        let mut acc = 0u32;
+        for (v, &n) in hist[c].iter().enumerate() {
+            acc += n;
+            if acc * 2 >= count {
+                key[c] = v as u8;
+                break;
+            }
+        }
+    }
+    key
+}
+
+/// Chroma key for green/blue-screen shots: key alpha (0–1) and the
+/// spill-suppressed RGBA (alpha channel untouched), no neural mask needed.
+///
+/// Works in the CbCr plane of full-range BT.601 YCbCr, so shading and
+/// shadows on the screen (luma changes) still key cleanly:
+///
+/// 1. Alpha from chroma distance d to `key` (given, or from
+///    `detect_key_color`): 0 below `tolerance`, 1 above `tolerance + softness`,
+///    linear in between (0–255 CbCr units)
+/// 2. Spill suppression: the component of each pixel's chroma along the key's
+///    chroma direction is removed (scaled by `spill`), keeping luma — the
+///    arbitrary-key generalization of g = min(g, max(r, b))
+///
+/// The alpha is a raw key; the caller runs it through the usual refinement.
+pub fn chroma_key(
+    rgba: &[u8],
+    key: [u8; 3],
+    tolerance: f32,
+    softness: f32,
+    spill: f32,
+) -> (Vec<f32>, Vec<u8>) {
+    let npx = rgba.len() / 4;
+    let (_, kcb, kcr) = to_ycbcr(key[0], key[1], key[2]);
+    let klen = (kcb * kcb + kcr * kcr).sqrt();
+    let (kx, ky) = if klen > 0.0 {
+        (kcb / klen, kcr / klen)
+    } else {
+        (0.0, 0.0)
+    };
+    let tolerance = tolerance.max(0.0);
+    let softness = softness.max(1e-3);
+    let spill = spill.clamp(0.0, 1.0);
+
+    let mut alpha = vec![0.0f32; npx];
+    let mut out = rgba.to_vec();
+    for (i, a_out) in alpha.iter_mut().enumerate() {
+        let off = i * 4;
+        let (y, cb, cr) = to_ycbcr(rgba[off], rgba[off + 1], rgba[off + 2]);
+
+        let dcb = cb - kcb;
+        let dcr = cr - kcr;
+        let d = (dcb * dcb + dcr * dcr).sqrt();
+        let a = ((d - tolerance) / softness).clamp(0.0, 1.0);
+        *a_out = a;
+
+        if a <= 0.0 || spill <= 0.0 {
+            continue;
+        }
+        let s = cb * kx + cr * ky;
+        if s <= 0.0 {
+            continue;
+        }
+        let cb = cb - kx * s * spill;
+        let cr = cr - ky * s * spill;
+        out[off] = (y + 1.402 * cr).round().clamp(0.0, 255.0) as u8;
+        out[off + 1] = (y - 0.344136 * cb - 0.714136 * cr)
+            .round()
+            .clamp(0.0, 255.0) as u8;
+        out[off + 2] = (y + 1.772 * cb).round().clamp(0.0, 255.0) as u8;
+    }
+
+    (alpha, out)
+}
+
+#[inline]
+fn to_ycbcr(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r = r as f32;
+    let g = g as f32;
+    let b = b as f32;
+    (
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.168736 * r - 0.331264 * g + 0.5 * b,
+        0.5 * r - 0.418688 * g - 0.081312 * b,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREEN: [u8; 3] = [40, 200, 60];
+
+    #[test]
+    fn test_key_color_is_transparent() {
+        let rgba = [GREEN[0], GREEN[1], GREEN[2], 255].repeat(4);
+        let (alpha, out) = chroma_key(&rgba, GREEN, 20.0, 10.0, 1.0);
+        assert!(alpha.iter().all(|&a| a == 0.0));
+        assert_eq!(out, rgba);
+    }
+
+    #[test]
+    fn test_far_subject_is_opaque() {
+        // Red and a skin tone are far from green in CbCr
+        let rgba = [220, 40, 40, 255, 210, 160, 130, 255];
+        let (alpha, _) = chroma_key(&rgba, GREEN, 20.0, 10.0, 1.0);
+        assert_eq!(alpha, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_soft_edge_between_tolerance_and_softness() {
+        // Halfway between the key and gray in CbCr
+        let (_, kcb, kcr) = to_ycbcr(GREEN[0], GREEN[1], GREEN[2]);
+        let d = (kcb * kcb + kcr * kcr).sqrt();
+        let (alpha, _) = chroma_key(&[128, 128, 128, 255], GREEN, d * 0.5, d, 0.0);
+        assert!((alpha[0] - 0.5).abs() < 0.02, "{}", alpha[0]);
+    }
+
+    #[test]
+    fn test_spill_suppression_lowers_key_channel() {
+        // Light skin with a green cast from the screen
+        let rgba = [170, 190, 140, 255];
+        let (alpha, out) = chroma_key(&rgba, GREEN, 10.0, 10.0, 1.0);
+        assert_eq!(alpha[0], 1.0);
+        assert!(out[1] < rgba[1], "{out:?}");
+        // Luma is kept
+        let (y0, _, _) = to_ycbcr(rgba[0], rgba[1], rgba[2]);
+        let (y1, _, _) = to_ycbcr(out[0], out[1], out[2]);
+        assert!((y0 - y1).abs() < 1.5, "{y0} vs {y1}");
+
+        let (_, kept) = chroma_key(&rgba, GREEN, 10.0, 10.0, 0.0);
+        assert_eq!(kept, rgba);
+    }
+
+    #[test]
+    fn test_detect_key_color_from_border() {
+        // Green frame around a red subject that covers most of the image
+        let (w, h) = (20, 20);
+        let mut rgba = Vec::with_capacity(w * h * 4);
+        for y in 0..h {
+            for x in 0..w {
+                let border = x < 2 || y < 2 || x >= w - 2 || y >= h - 2;
+                let c = if border { GREEN } else { [220, 40, 40] };
+                rgba.extend_from_slice(&[c[0], c[1], c[2], 255]);
+            }
+        }
+        assert_eq!(detect_key_color(&rgba, w, h), GREEN);
+    }
+}
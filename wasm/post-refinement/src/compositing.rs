@@ -0,0 +1,322 @@
+use crate::{autocrop, bokeh, outline, pack_with_dims};
+use wasm_bindgen::prelude::*;
+
+/// Seamless cloning: paste a cutout into a new scene without visible seams.
+///
+/// Solves a Poisson equation so the pasted region keeps the cutout's
+/// gradients while matching `target_rgba` along its boundary. The cutout's
+/// top-left corner lands at (`offset_x`, `offset_y`) in the target.
+/// `mixed_gradients` keeps the stronger of the cutout and target gradients,
+/// letting background texture show through flat areas. Returns RGBA at the
+/// target size; invalid input returns the target copy.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn seamless_clone(
+    cutout_rgba: &[u8],
+    cutout_width: u32,
+    cutout_height: u32,
+    target_rgba: &[u8],
+    target_width: u32,
+    target_height: u32,
+    offset_x: i32,
+    offset_y: i32,
+    mixed_gradients: bool,
+) -> Vec<u8> {
+    let sw = cutout_width as usize;
+    let sh = cutout_height as usize;
+    let dw = target_width as usize;
+    let dh = target_height as usize;
+    if cutout_rgba.len() != sw * sh * 4 || target_rgba.len() != dw * dh * 4 {
+        return target_rgba.to_vec();
+    }
+
+    crate::seamless_clone::seamless_clone(
+        cutout_rgba,
+        sw,
+        sh,
+        target_rgba,
+        dw,
+        dh,
+        offset_x as isize,
+        offset_y as isize,
+        mixed_gradients,
+    )
+}
+
+/// Object removal: fill the hole marked by `hole_mask_rgba` (alpha ≥ 128).
+///
+/// `method` is "diffusion" (Telea fast-marching extrapolation over `radius`
+/// pixels — best for thin scratches, text and wires), "patchmatch" (texture
+/// synthesis from `patch_size` patches, refined for `iterations` rounds) or
+/// "auto" (picks by hole thickness). Returns RGBA with only the hole changed;
+/// invalid input returns the input copy.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn inpaint(
+    rgba: &[u8],
+    hole_mask_rgba: &[u8],
+    width: u32,
+    height: u32,
+    method: &str,
+    radius: u32,
+    patch_size: u32,
+    iterations: u32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    let expected = w * h * 4;
+    if rgba.len() != expected || hole_mask_rgba.len() != expected {
+        return rgba.to_vec();
+    }
+
+    let hole: Vec<bool> = hole_mask_rgba
+        .chunks_exact(4)
+        .map(|p| p[3] >= 128)
+        .collect();
+    crate::inpaint::inpaint(
+        rgba,
+        &hole,
+        w,
+        h,
+        crate::inpaint::Method::from_name(method),
+        radius as usize,
+        patch_size as usize,
+        iterations as usize,
+    )
+}
+
+/// Portrait-style background blur using the refined mask.
+///
+/// Keeps the subject (alpha of `mask_rgba`) sharp and replaces the background
+/// with a lens-like bokeh blur of the original. `shape` is "disc" or "hexagon".
+/// Background colors are gathered with mask-aware normalized convolution, so
+/// the subject never smears into its own halo. `radius` is capped at a
+/// quarter of the shorter side; 0 skips the blur. Returns opaque RGBA.
+#[wasm_bindgen]
+pub fn portrait_blur(
+    mask_rgba: &[u8],
+    original_rgba: &[u8],
+    width: u32,
+    height: u32,
+    radius: u32,
+    shape: &str,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    let npx = w * h;
+
+    if mask_rgba.len() != npx * 4 || original_rgba.len() != npx * 4 {
+        return original_rgba.to_vec();
+    }
+
+    let inv255 = 1.0 / 255.0;
+    let alpha: Vec<f32> = (0..npx)
+        .map(|i| mask_rgba[i * 4 + 3] as f32 * inv255)
+        .collect();
+
+    bokeh::portrait_blur(
+        original_rgba,
+        &alpha,
+        w,
+        h,
+        radius as usize,
+        bokeh::BokehShape::from_name(shape),
+    )
+}
+
+/// Sticker-style outline strokes around the cutout.
+///
+/// `stroke_widths[k]` (pixels) and `stroke_colors[3k..3k+3]` (RGB) describe
+/// strokes stacked outward from the silhouette; `softness` is the width of the
+/// anti-aliased edge. Distances come from an exact Euclidean distance
+/// transform, so strokes stay round at any width.
+///
+/// The canvas grows by the total stroke width on every side; the total width
+/// and the softness are capped at half the longer image side. Returns a packed
+/// buffer `[width_le_u32, height_le_u32, ...rgba_pixels]`, or an empty Vec on
+/// invalid input.
+#[wasm_bindgen]
+pub fn sticker_outline(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    stroke_widths: &[f32],
+    stroke_colors: &[u8],
+    softness: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || stroke_colors.len() != stroke_widths.len() * 3 {
+        return Vec::new();
+    }
+
+    let colors: Vec<[u8; 3]> = stroke_colors
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect();
+    let (out, ow, oh) = outline::sticker_outline(rgba, w, h, stroke_widths, &colors, softness);
+    pack_with_dims(&out, ow, oh)
+}
+
+/// Auto-crop the cutout to its subject.
+///
+/// 1. Subject: alpha above `alpha_threshold`, minus connected specks smaller
+///    than `min_speck_area` pixels — stray mask islands must not widen the box
+/// 2. Its bounding box, grown by `padding` (at most the longer image side)
+/// 3. With non-zero `aspect_width`/`aspect_height`, the box grows to that
+///    aspect ratio. The extra space goes around the subject either evenly
+///    (`placement` "center") or so the subject's center sits on a third line
+///    ("thirds": the vertical third line nearest the subject's original
+///    position, the upper horizontal one)
+/// 4. With a non-zero `canvas_width`×`canvas_height` (whose aspect ratio is
+///    used when none is given), the crop is resampled to fit that canvas,
+///    centered, with premultiplied alpha and a tent filter widened for
+///    reductions
+///
+/// Crop regions reaching past the image are filled with transparency.
+///
+/// Returns a packed buffer `[width_le_u32, height_le_u32, ...rgba_pixels]`,
+/// the whole image when no subject is found, or an empty Vec on invalid input
+/// or when the crop or canvas would exceed `MAX_CROP_PIXELS`.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn auto_crop(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    alpha_threshold: u8,
+    min_speck_area: u32,
+    padding: u32,
+    aspect_width: u32,
+    aspect_height: u32,
+    placement: &str,
+    canvas_width: u32,
+    canvas_height: u32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return Vec::new();
+    }
+
+    let canvas = canvas_width > 0 && canvas_height > 0;
+    let (dw, dh) = (canvas_width as usize, canvas_height as usize);
+    if canvas && dw.saturating_mul(dh) > autocrop::MAX_CROP_PIXELS {
+        return Vec::new();
+    }
+    // A subject box padded by more than the longer side is all transparency
+    let Ok(pad) = isize::try_from((padding as usize).min(w.max(h))) else {
+        return Vec::new();
+    };
+    let aspect = if aspect_width > 0 && aspect_height > 0 {
+        Some(aspect_width as f32 / aspect_height as f32)
+    } else if canvas {
+        Some(canvas_width as f32 / canvas_height as f32)
+    } else {
+        None
+    };
+
+    let rect = match autocrop::subject_bounds(rgba, w, h, alpha_threshold, min_speck_area as usize)
+    {
+        Some(b) => {
+            let padded = autocrop::Rect {
+                x0: b.x0 - pad,
+                y0: b.y0 - pad,
+                x1: b.x1 + pad,
+                y1: b.y1 + pad,
+            };
+            match aspect {
+                Some(a) => {
+                    let placement = autocrop::Placement::from_name(placement);
+                    match autocrop::fit_aspect(padded, a, placement, w) {
+                        Some(rect) => rect,
+                        None => return Vec::new(),
+                    }
+                }
+                None => padded,
+            }
+        }
+        None => autocrop::Rect {
+            x0: 0,
+            y0: 0,
+            x1: w as isize,
+            y1: h as isize,
+        },
+    };
+
+    let cw = rect.w() as usize;
+    let ch = rect.h() as usize;
+    if cw * ch > autocrop::MAX_CROP_PIXELS.max(w * h) {
+        return Vec::new();
+    }
+    let cropped = autocrop::extract(rgba, w, h, rect);
+    if canvas {
+        let out = autocrop::place_on_canvas(&cropped, cw, ch, dw, dh);
+        pack_with_dims(&out, dw, dh)
+    } else {
+        pack_with_dims(&cropped, cw, ch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dims(packed: &[u8]) -> (u32, u32) {
+        (
+            u32::from_le_bytes(packed[0..4].try_into().unwrap()),
+            u32::from_le_bytes(packed[4..8].try_into().unwrap()),
+        )
+    }
+
+    /// 16×16 transparent image with an opaque 4×4 square at (6, 6).
+    fn square() -> Vec<u8> {
+        let mut rgba = vec![0u8; 16 * 16 * 4];
+        for y in 6..10 {
+            for x in 6..10 {
+                rgba[(y * 16 + x) * 4 + 3] = 255;
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_padding() {
+        let out = auto_crop(&square(), 16, 16, 0, 1, 3, 0, 0, "center", 0, 0);
+        assert_eq!(dims(&out), (10, 10));
+        // The subject sits `padding` pixels in
+        let px = &out[8..];
+        assert_eq!(px[(3 * 10 + 3) * 4 + 3], 255);
+        assert_eq!(px[(2 * 10 + 3) * 4 + 3], 0);
+    }
+
+    #[test]
+    fn test_huge_padding_is_clamped() {
+        let out = auto_crop(&square(), 16, 16, 0, 1, u32::MAX, 0, 0, "center", 0, 0);
+        assert_eq!(dims(&out), (4 + 2 * 16, 4 + 2 * 16));
+    }
+
+    #[test]
+    fn test_no_subject_keeps_whole_image() {
+        let rgba = vec![0u8; 16 * 16 * 4];
+        let out = auto_crop(&rgba, 16, 16, 0, 1, 3, 0, 0, "center", 0, 0);
+        assert_eq!(dims(&out), (16, 16));
+    }
+
+    #[test]
+    fn test_oversized_output_rejected() {
+        let rgba = square();
+        assert!(auto_crop(&rgba, 16, 16, 0, 1, 0, 0, 0, "center", u32::MAX, u32::MAX).is_empty());
+        assert!(auto_crop(&rgba, 16, 16, 0, 1, 0, u32::MAX, 1, "center", 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_canvas_fit() {
+        let out = auto_crop(&square(), 16, 16, 0, 1, 0, 0, 0, "center", 8, 4);
+        assert_eq!(dims(&out), (8, 4));
+        let px = &out[8..];
+        // 4×4 subject scaled to fit, centered with transparent sides
+        assert_eq!(px[3], 0);
+        assert_eq!(px[(2 * 8 + 4) * 4 + 3], 255);
+    }
+}
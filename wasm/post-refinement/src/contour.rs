@@ -0,0 +1,475 @@
+use crate::distance::euclidean_distance;
+
+/// Turn angle (radians) above which a simplified vertex is kept as a corner.
+const CORNER_ANGLE: f32 = 1.0;
+/// Loops enclosing less area than this (px²) are dropped as noise.
+const MIN_LOOP_AREA: f32 = 1.0;
+
+const NO_EDGE: u32 = u32::MAX;
+
+pub struct Contour {
+    /// Dense marching-squares loop (closed, first point not repeated)
+    pub dense: Vec<[f32; 2]>,
+    /// Indices into `dense` of the simplified polygon's vertices
+    pub keep: Vec<usize>,
+    pub hole: bool,
+}
+
+impl Contour {
+    pub fn simplified(&self) -> Vec<[f32; 2]> {
+        self.keep.iter().map(|&i| self.dense[i]).collect()
+    }
+}
+
+/// Trace and simplify the outline of alpha > `threshold`, grown by `offset` px.
+///
+/// 1. Scalar field whose zero level is the outline: alpha − threshold (sub-pixel
+///    from the soft edge), or a signed Euclidean distance to the thresholded
+///    mask plus `offset` when the outline should grow/shrink. `offset` is
+///    clamped to ± the longer image side, past which the outline is a plain
+///    rounded box (or gone) and the padded grid would only grow
+/// 2. Marching squares on a padded grid so every contour closes. Segments are
+///    oriented with the subject on one side, so holes wind opposite to outer
+///    contours and a nonzero fill renders them correctly. Saddles are resolved
+///    with the cell center value
+/// 3. Ramer–Douglas–Peucker simplification of each closed loop
+///
+/// `svg_path` optionally fits cubic Béziers to the dense points between the
+/// simplified vertices.
+pub fn trace_contours(
+    rgba: &[u8],
+    w: usize,
+    h: usize,
+    threshold: u8,
+    offset: f32,
+    tolerance: f32,
+) -> Vec<Contour> {
+    let limit = w.max(h) as f32;
+    let offset = offset.clamp(-limit, limit);
+    let pad = offset.max(0.0).ceil() as usize + 1;
+    let gw = w + 2 * pad;
+    let gh = h + 2 * pad;
+    let field = build_field(rgba, w, h, threshold, offset, pad);
+
+    let loops = march(&field, gw, gh);
+    let mut contours = Vec::with_capacity(loops.len());
+    for mut pts in loops {
+        for p in pts.iter_mut() {
+            p[0] += 0.5 - pad as f32;
+            p[1] += 0.5 - pad as f32;
+        }
+        let area = signed_area(&pts);
+        if area.abs() < MIN_LOOP_AREA {
+            continue;
+        }
+        let keep = simplify_closed(&pts, tolerance.max(0.0));
+        if keep.len() < 3 {
+            continue;
+        }
+        contours.push(Contour {
+            dense: pts,
+            keep,
+            hole: area > 0.0,
+        });
+    }
+    contours
+}
+
+/// Zero-level field on the padded grid: > 0 inside the outline.
+fn build_field(
+    rgba: &[u8],
+    w: usize,
+    h: usize,
+    threshold: u8,
+    offset: f32,
+    pad: usize,
+) -> Vec<f32> {
+    let gw = w + 2 * pad;
+    let gh = h + 2 * pad;
+    let thr = threshold as f32;
+
+    if offset == 0.0 {
+        let mut field = vec![-thr / 255.0; gw * gh];
+        for y in 0..h {
+            for x in 0..w {
+                field[(y + pad) * gw + x + pad] = (rgba[(y * w + x) * 4 + 3] as f32 - thr) / 255.0;
+            }
+        }
+        return field;
+    }
+
+    let mut inside = vec![false; gw * gh];
+    for y in 0..h {
+        for x in 0..w {
+            inside[(y + pad) * gw + x + pad] = rgba[(y * w + x) * 4 + 3] > threshold;
+        }
+    }
+    let outside: Vec<bool> = inside.iter().map(|&v| !v).collect();
+    let to_inside = euclidean_distance(&inside, gw, gh);
+    let to_outside = euclidean_distance(&outside, gw, gh);
+
+    (0..gw * gh)
+        .map(|i| {
+            let sd = if inside[i] {
+                to_outside[i] - 0.5
+            } else {
+                // f32::MAX without any subject: stays far outside
+                0.5 - to_inside[i].min(1e6)
+            };
+            sd + offset
+        })
+        .collect()
+}
+
+/// Marching squares → closed loops in grid coordinates.
+fn march(field: &[f32], gw: usize, gh: usize) -> Vec<Vec<[f32; 2]>> {
+    // Edge ids: horizontal edge right of (x, y) = 2i, vertical edge below = 2i + 1
+    let mut next = vec![NO_EDGE; gw * gh * 2];
+    let mut pos = vec![[0.0f32; 2]; gw * gh * 2];
+
+    for cy in 0..gh - 1 {
+        for cx in 0..gw - 1 {
+            // Corners clockwise from top-left
+            let corners = [(cx, cy), (cx + 1, cy), (cx + 1, cy + 1), (cx, cy + 1)];
+            let v = corners.map(|(x, y)| field[y * gw + x]);
+            let inside = v.map(|f| f > 0.0);
+            if inside.iter().all(|&b| b) || inside.iter().all(|&b| !b) {
+                continue;
+            }
+
+            // Edge k joins corner k and k + 1 (top, right, bottom, left)
+            let edge_ids = [
+                (cy * gw + cx) * 2,
+                (cy * gw + cx + 1) * 2 + 1,
+                ((cy + 1) * gw + cx) * 2,
+                (cy * gw + cx) * 2 + 1,
+            ];
+            let mut entries = Vec::with_capacity(2);
+            let mut exits = Vec::with_capacity(2);
+            for k in 0..4 {
+                let k1 = (k + 1) % 4;
+                if inside[k] == inside[k1] {
+                    continue;
+                }
+                let t = v[k] / (v[k] - v[k1]);
+                let (x0, y0) = corners[k];
+                let (x1, y1) = corners[k1];
+                pos[edge_ids[k]] = [
+                    x0 as f32 + t * (x1 as f32 - x0 as f32),
+                    y0 as f32 + t * (y1 as f32 - y0 as f32),
+                ];
+                if inside[k1] {
+                    entries.push(k);
+                } else {
+                    exits.push(k);
+                }
+            }
+
+            if entries.len() == 1 {
+                next[edge_ids[entries[0]]] = edge_ids[exits[0]] as u32;
+                continue;
+            }
+
+            // Saddle: join the inside corners through the center when it is
+            // inside, otherwise keep them separate
+            let center_inside = v.iter().sum::<f32>() > 0.0;
+            for &e in &entries {
+                let x = if center_inside {
+                    (e + 3) % 4
+                } else {
+                    (e + 1) % 4
+                };
+                next[edge_ids[e]] = edge_ids[x] as u32;
+            }
+        }
+    }
+
+    // Follow the links into loops
+    let mut loops = Vec::new();
+    for start in 0..next.len() {
+        if next[start] == NO_EDGE {
+            continue;
+        }
+        let mut pts = Vec::new();
+        let mut e = start;
+        while next[e] != NO_EDGE {
+            pts.push(pos[e]);
+            let n = next[e] as usize;
+            next[e] = NO_EDGE;
+            e = n;
+        }
+        loops.push(pts);
+    }
+    loops
+}
+
+/// Shoelace area (y down): negative for outer contours, positive for holes.
+fn signed_area(pts: &[[f32; 2]]) -> f32 {
+    let n = pts.len();
+    let mut sum = 0.0f32;
+    for i in 0..n {
+        let a = pts[i];
+        let b = pts[(i + 1) % n];
+        sum += a[0] * b[1] - b[0] * a[1];
+    }
+    sum * 0.5
+}
+
+/// Ramer–Douglas–Peucker on a closed loop; returns kept indices in order.
+///
+/// The loop is split at the point farthest from the first one, then each
+/// half is simplified as an open polyline.
+fn simplify_closed(pts: &[[f32; 2]], epsilon: f32) -> Vec<usize> {
+    let n = pts.len();
+    if n < 3 {
+        return (0..n).collect();
+    }
+    let far = (1..n)
+        .max_by(|&a, &b| dist2(pts[a], pts[0]).total_cmp(&dist2(pts[b], pts[0])))
+        .unwrap_or(n / 2);
+
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[far] = true;
+
+    // Spans are [lo, hi] with hi == n meaning point 0
+    let mut stack = vec![(0usize, far), (far, n)];
+    while let Some((lo, hi)) = stack.pop() {
+        if hi - lo < 2 {
+            continue;
+        }
+        let a = pts[lo];
+        let b = pts[hi % n];
+        let mut best = 0.0f32;
+        let mut best_i = lo;
+        for (i, &p) in pts.iter().enumerate().take(hi).skip(lo + 1) {
+            let d = segment_distance(p, a, b);
+            if d > best {
+                best = d;
+                best_i = i;
+            }
+        }
+        if best > epsilon {
+            keep[best_i] = true;
+            stack.push((lo, best_i));
+            stack.push((best_i, hi));
+        }
+    }
+
+    (0..n).filter(|&i| keep[i]).collect()
+}
+
+/// SVG path data for all contours (nonzero fill rule).
+///
+/// With `smooth`, each span between simplified vertices becomes a cubic
+/// Bézier fitted to the dense outline; otherwise the polygon is emitted.
+pub fn svg_path(contours: &[Contour], smooth: bool) -> String {
+    let mut d = String::new();
+    for c in contours {
+        let verts = c.simplified();
+        let n = verts.len();
+        if !d.is_empty() {
+            d.push(' ');
+        }
+        d.push_str(&format!("M{:.2},{:.2}", verts[0][0], verts[0][1]));
+
+        if !smooth {
+            for v in &verts[1..] {
+                d.push_str(&format!(" L{:.2},{:.2}", v[0], v[1]));
+            }
+            d.push_str(" Z");
+            continue;
+        }
+
+        // Tangent leaving / entering each vertex
+        let mut t_out = vec![[0.0f32; 2]; n];
+        let mut t_in = vec![[0.0f32; 2]; n];
+        for i in 0..n {
+            let prev = verts[(i + n - 1) % n];
+            let next = verts[(i + 1) % n];
+            let d_in = normalize(sub(verts[i], prev));
+            let d_out = normalize(sub(next, verts[i]));
+            let turn = (d_in[0] * d_out[0] + d_in[1] * d_out[1])
+                .clamp(-1.0, 1.0)
+                .acos();
+            if turn > CORNER_ANGLE {
+                t_in[i] = d_in;
+                t_out[i] = d_out;
+            } else {
+                let t = normalize(sub(next, prev));
+                t_in[i] = t;
+                t_out[i] = t;
+            }
+        }
+
+        let m = c.dense.len();
+        for (k, &lo) in c.keep.iter().enumerate() {
+            let k1 = (k + 1) % n;
+            let hi = if k1 == 0 { m } else { c.keep[k1] };
+            let span: Vec<[f32; 2]> = (lo..=hi).map(|i| c.dense[i % m]).collect();
+            let [c1, c2] = fit_cubic(&span, t_out[k], t_in[k1]);
+            let p = verts[k1];
+            d.push_str(&format!(
+                " C{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}",
+                c1[0], c1[1], c2[0], c2[1], p[0], p[1]
+            ));
+        }
+        d.push_str(" Z");
+    }
+    d
+}
+
+/// Control points of the cubic through `span`'s endpoints along the given
+/// tangents, with handle lengths from least squares (Schneider, 1990).
+fn fit_cubic(span: &[[f32; 2]], t1: [f32; 2], t2: [f32; 2]) -> [[f32; 2]; 2] {
+    let p0 = span[0];
+    let p3 = span[span.len() - 1];
+    let chord = dist2(p0, p3).sqrt();
+    let fallback = chord / 3.0;
+
+    // Chord-length parameterization
+    let mut u = vec![0.0f32; span.len()];
+    for i in 1..span.len() {
+        u[i] = u[i - 1] + dist2(span[i], span[i - 1]).sqrt();
+    }
+    let total = u[span.len() - 1];
+
+    let (mut a1, mut a2) = (fallback, fallback);
+    if span.len() > 2 && total > 0.0 {
+        let mut c = [[0.0f32; 2]; 2];
+        let mut x = [0.0f32; 2];
+        for (i, &pt) in span.iter().enumerate() {
+            let t = u[i] / total;
+            let s = 1.0 - t;
+            let b0 = s * s * s;
+            let b1 = 3.0 * t * s * s;
+            let b2 = 3.0 * t * t * s;
+            let b3 = t * t * t;
+            let v1 = [t1[0] * b1, t1[1] * b1];
+            let v2 = [-t2[0] * b2, -t2[1] * b2];
+            c[0][0] += dot(v1, v1);
+            c[0][1] += dot(v1, v2);
+            c[1][1] += dot(v2, v2);
+            let base = [
+                p0[0] * (b0 + b1) + p3[0] * (b2 + b3),
+                p0[1] * (b0 + b1) + p3[1] * (b2 + b3),
+            ];
+            let r = sub(pt, base);
+            x[0] += dot(r, v1);
+            x[1] += dot(r, v2);
+        }
+        c[1][0] = c[0][1];
+        let det = c[0][0] * c[1][1] - c[0][1] * c[1][0];
+        if det.abs() > 1e-9 {
+            let l1 = (x[0] * c[1][1] - c[0][1] * x[1]) / det;
+            let l2 = (c[0][0] * x[1] - x[0] * c[1][0]) / det;
+            // Degenerate or looping handles fall back to thirds of the chord
+            if l1 > 1e-3 && l2 > 1e-3 && l1 < chord && l2 < chord {
+                a1 = l1;
+                a2 = l2;
+            }
+        }
+    }
+
+    [
+        [p0[0] + t1[0] * a1, p0[1] + t1[1] * a1],
+        [p3[0] - t2[0] * a2, p3[1] - t2[1] * a2],
+    ]
+}
+
+#[inline]
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+#[inline]
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+#[inline]
+fn dist2(a: [f32; 2], b: [f32; 2]) -> f32 {
+    let d = sub(a, b);
+    dot(d, d)
+}
+
+#[inline]
+fn normalize(v: [f32; 2]) -> [f32; 2] {
+    let len = dot(v, v).sqrt();
+    if len > 0.0 {
+        [v[0] / len, v[1] / len]
+    } else {
+        [0.0, 0.0]
+    }
+}
+
+/// Distance from `p` to segment `ab`.
+fn segment_distance(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let ab = sub(b, a);
+    let len2 = dot(ab, ab);
+    if len2 == 0.0 {
+        return dist2(p, a).sqrt();
+    }
+    let t = (dot(sub(p, a), ab) / len2).clamp(0.0, 1.0);
+    dist2(p, [a[0] + ab[0] * t, a[1] + ab[1] * t]).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 24×24 canvas with an opaque square ring: outer 16×16, inner hole 6×6.
+    fn square_ring() -> Vec<u8> {
+        let mut rgba = vec![0u8; 24 * 24 * 4];
+        for y in 4..20 {
+            for x in 4..20 {
+                if !(9..15).contains(&x) || !(9..15).contains(&y) {
+                    rgba[(y * 24 + x) * 4 + 3] = 255;
+                }
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_ring_has_outer_and_hole_with_opposite_winding() {
+        let contours = trace_contours(&square_ring(), 24, 24, 128, 0.0, 0.5);
+        assert_eq!(contours.len(), 2);
+        let outer = contours.iter().find(|c| !c.hole).unwrap();
+        let hole = contours.iter().find(|c| c.hole).unwrap();
+
+        let (ao, ah) = (signed_area(&outer.dense), signed_area(&hole.dense));
+        assert!(ao < 0.0 && ah > 0.0, "areas {ao}, {ah}");
+        // Edges sit halfway between opaque and transparent pixel centers
+        assert!((ao.abs() - 16.0 * 16.0).abs() < 4.0, "outer area {ao}");
+        assert!((ah - 6.0 * 6.0).abs() < 4.0, "hole area {ah}");
+
+        // Squares simplify to their 4 corners
+        assert_eq!(outer.keep.len(), 4);
+        assert_eq!(hole.keep.len(), 4);
+    }
+
+    #[test]
+    fn test_offset_grows_outline() {
+        let grown = trace_contours(&square_ring(), 24, 24, 128, 2.0, 0.5);
+        let outer = grown.iter().find(|c| !c.hole).unwrap();
+        let side = 16.0 + 2.0 * 2.0;
+        assert!(signed_area(&outer.dense).abs() > side * side - 20.0);
+        // The 6×6 hole shrinks to 2×2
+        let hole = grown.iter().find(|c| c.hole).unwrap();
+        assert!(signed_area(&hole.dense) < 9.0);
+    }
+
+    #[test]
+    fn test_huge_offset_is_clamped() {
+        let grown = trace_contours(&square_ring(), 24, 24, 128, 1e12, 0.5);
+        assert_eq!(grown.len(), 1);
+        // Grown by the longer side: the 16×16 square becomes a 64×64 box with
+        // corners rounded by 24, area 64² − (4 − π)·24²
+        let area = signed_area(&grown[0].dense).abs();
+        let exact = 64.0 * 64.0 - (4.0 - std::f32::consts::PI) * 24.0 * 24.0;
+        assert!((area - exact).abs() < 40.0, "area {area} vs {exact}");
+
+        assert!(trace_contours(&square_ring(), 24, 24, 128, -1e12, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::fast_guided_filter::fast_guided_filter;
+use std::collections::VecDeque;
+
+const OMEGA: f32 = 0.95;
+const T_MIN: f32 = 0.1;
+const BRIGHTEST_FRACTION: f32 = 0.001;
+/// Floor on A's channels so the normalization never divides by ~0.
+const A_MIN: f32 = 0.05;
+const GUIDE_EPS: f32 = 1e-3;
+
+/// Remove haze from `rgba` in place with the dark channel prior (He, Sun &
+/// Tang, "Single Image Haze Removal Using Dark Channel Prior", CVPR 2009).
+///
+/// Haze model: I = J·t + A·(1 − t), with J the haze-free scene, A the
+/// atmospheric light and t the transmission. In haze-free outdoor patches
+/// some channel is almost always near zero (the dark channel), so
+///
+///   t = 1 − ω · min_patch(min_c I_c / A_c)
+///
+/// - A: mean color of the 0.1% of pixels with the brightest dark channel
+///   (the most haze-opaque region, which skips bright white objects)
+/// - t is refined with the fast guided filter on luminance (He & Sun, 2015)
+///   instead of the paper's soft matting — edge-aligned, no blocky halos
+/// - J = (I − A) / max(t, `T_MIN`) + A; the floor keeps dense haze and sky
+///   from amplifying noise
+///
+/// `strength` scales ω (0 = unchanged, 1 = ω 0.95, the paper's value that
+/// keeps a trace of haze for depth). Fully transparent pixels are ignored
+/// when estimating A; alpha is passed through.
+pub fn dehaze(rgba: &mut [u8], w: usize, h: usize, strength: f32) {
+    let strength = strength.clamp(0.0, 1.0);
+    if strength == 0.0 || w == 0 || h == 0 {
+        return;
+    }
+    let inv255 = 1.0 / 255.0;
+    // 15×15 patches at ~600 px (the paper's setting), scaled with the image
+    let patch_radius = (w.min(h) / 80).clamp(2, 15);
+
+    // === Dark channel of I → atmospheric light ===
+    let min_rgb: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| p[0].min(p[1]).min(p[2]) as f32 * inv255)
+        .collect();
+    let dark = min_filter(&min_rgb, w, h, patch_radius);
+    let air = atmospheric_light(rgba, &dark);
+
+    // === Transmission from the dark channel of I / A ===
+    let normalized: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| {
+            (0..3)
+                .map(|c| p[c] as f32 * inv255 / air[c])
+                .fold(f32::MAX, f32::min)
+        })
+        .collect();
+    let omega = OMEGA * strength;
+    let coarse: Vec<f32> = min_filter(&normalized, w, h, patch_radius)
+        .iter()
+        .map(|&d| 1.0 - omega * d)
+        .collect();
+
+    // === Edge-aligned refinement (guided by luminance) ===
+    let guide: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| (p[0] as f32 * 0.2126 + p[1] as f32 * 0.7152 + p[2] as f32 * 0.0722) * inv255)
+        .collect();
+    let subsample = 4usize.min(w.min(h) / 8).max(1);
+    let transmission = fast_guided_filter(
+        &guide,
+        &coarse,
+        w,
+        h,
+        patch_radius * 4,
+        GUIDE_EPS,
+        subsample,
+    );
+
+    // === Scene radiance ===
+    for (px, &t) in rgba.chunks_exact_mut(4).zip(&transmission) {
+        let inv_t = 1.0 / t.max(T_MIN);
+        for c in 0..3 {
+            let i = px[c] as f32 * inv255;
+            let j = (i - air[c]) * inv_t + air[c];
+            px[c] = (j * 255.0 + 0.5).clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Mean color (0–1) of the visible pixels with the brightest dark channel.
+fn atmospheric_light(rgba: &[u8], dark: &[f32]) -> [f32; 3] {
+    let mut candidates: Vec<usize> = (0..dark.len()).filter(|&i| rgba[i * 4 + 3] > 0).collect();
+    if candidates.is_empty() {
+        return [1.0; 3];
+    }
+    let n = ((candidates.len() as f32 * BRIGHTEST_FRACTION).ceil() as usize).max(1);
+    candidates.select_nth_unstable_by(n - 1, |&a, &b| dark[b].total_cmp(&dark[a]));
+
+    let mut sum = [0.0f32; 3];
+    for &i in &candidates[..n] {
+        for c in 0..3 {
+            sum[c] += rgba[i * 4 + c] as f32;
+        }
+    }
+    sum.map(|s| (s / (n as f32 * 255.0)).max(A_MIN))
+}
+
+/// Separable (2r+1)² minimum filter, monotonic deque per row/column: O(1)
+/// per pixel whatever the radius.
+fn min_filter(src: &[f32], w: usize, h: usize, r: usize) -> Vec<f32> {
+    let mut tmp = vec![0.0f32; w * h];
+    for y in 0..h {
+        let row = y * w;
+        min_1d(&src[row..row + w], &mut tmp[row..row + w], r);
+    }
+    let mut out = vec![0.0f32; w * h];
+    let mut column = vec![0.0f32; h];
+    let mut column_out = vec![0.0f32; h];
+    for x in 0..w {
+        for y in 0..h {
+            column[y] = tmp[y * w + x];
+        }
+        min_1d(&column, &mut column_out, r);
+        for y in 0..h {
+            out[y * w + x] = column_out[y];
+        }
+    }
+    out
+}
+
+/// Sliding-window minimum over [i − r, i + r], clamped to the signal.
+fn min_1d(src: &[f32], dst: &mut [f32], r: usize) {
+    let n = src.len();
+    let mut window: VecDeque<usize> = VecDeque::with_capacity(2 * r + 2);
+    let mut next = 0usize;
+    for (i, out) in dst.iter_mut().enumerate() {
+        // Admit samples up to i + r
+        while next < n && next <= i + r {
+            while window.back().is_some_and(|&j| src[j] >= src[next]) {
+                window.pop_back();
+            }
+            window.push_back(next);
+            next += 1;
+        }
+        // Drop samples before i − r
+        while window.front().is_some_and(|&j| j + r < i) {
+            window.pop_front();
+        }
+        *out = src[window[0]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 96×96 blocks of saturated colors: every pixel has a channel at 0, so
+    /// the dark channel of the haze-free scene is 0 everywhere. The top
+    /// `SKY` rows are the airlight color, where haze is opaque.
+    const SKY: usize = 16;
+    const AIR: f32 = 230.0;
+
+    fn scene() -> Vec<u8> {
+        const COLORS: [[u8; 3]; 4] = [[200, 30, 0], [0, 120, 180], [40, 0, 90], [90, 160, 0]];
+        let mut rgba = Vec::with_capacity(96 * 96 * 4);
+        for y in 0..96 {
+            for x in 0..96 {
+                let c = if y < SKY {
+                    [AIR as u8; 3]
+                } else {
+                    COLORS[(x / 16 + y / 16) % 4]
+                };
+                rgba.extend_from_slice(&[c[0], c[1], c[2], 255]);
+            }
+        }
+        rgba
+    }
+
+    /// I = J·t + A·(1 − t) with a uniform transmission and gray airlight.
+    fn hazy(scene: &[u8], t: f32, air: f32) -> Vec<u8> {
+        scene
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let f = |v: u8| (v as f32 * t + air * (1.0 - t) + 0.5) as u8;
+                [f(p[0]), f(p[1]), f(p[2]), p[3]]
+            })
+            .collect()
+    }
+
+    fn mean_abs_diff(a: &[u8], b: &[u8]) -> f32 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| x.abs_diff(*y) as f32)
+            .sum::<f32>()
+            / a.len() as f32
+    }
+
+    #[test]
+    fn test_haze_free_image_is_kept() {
+        let clean = scene();
+        let mut buf = clean.clone();
+        dehaze(&mut buf, 96, 96, 1.0);
+        let ground = SKY * 96 * 4;
+        assert!(mean_abs_diff(&buf[ground..], &clean[ground..]) < 1.0);
+    }
+
+    #[test]
+    fn test_synthetic_haze_is_removed() {
+        let clean = scene();
+        let mut buf = hazy(&clean, 0.6, AIR);
+        // Measured below the sky, which is all haze
+        let ground = SKY * 96 * 4;
+        let before = mean_abs_diff(&buf[ground..], &clean[ground..]);
+        dehaze(&mut buf, 96, 96, 1.0);
+        let after = mean_abs_diff(&buf[ground..], &clean[ground..]);
+        assert!(after < before * 0.25, "{before} → {after}");
+    }
+
+    #[test]
+    fn test_transmission_floor_limits_gain() {
+        // Dense haze: airlight gray 220 with sparse specks 6 levels darker.
+        // t drops below T_MIN, so a speck may move at most 6 / T_MIN away
+        // from A instead of being blown up by 1 / t
+        let speck = |i: usize| (i % 64).is_multiple_of(8) && (i / 64).is_multiple_of(8);
+        let mut buf: Vec<u8> = (0..64 * 64)
+            .flat_map(|i| {
+                let v = if speck(i) { 214 } else { 220 };
+                [v, v, v, 255]
+            })
+            .collect();
+        dehaze(&mut buf, 64, 64, 1.0);
+        for (i, p) in buf.chunks_exact(4).enumerate() {
+            let expected = if speck(i) { 220.0 - 6.0 / T_MIN } else { 220.0 };
+            assert!((p[0] as f32 - expected).abs() <= 1.0, "{i}: {}", p[0]);
+        }
+    }
+
+    #[test]
+    fn test_zero_strength_is_identity() {
+        let hazy = hazy(&scene(), 0.6, AIR);
+        let mut buf = hazy.clone();
+        dehaze(&mut buf, 96, 96, 0.0);
+        assert_eq!(buf, hazy);
+    }
+}
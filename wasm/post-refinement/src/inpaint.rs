@@ -0,0 +1,495 @@
+use crate::distance::euclidean_distance;
+use crate::shared_matting::spiral_offsets;
+
+const AUTO_THIN_RADIUS: f32 = 6.0;
+/// Propagation / random-search passes per EM iteration.
+const PM_SEARCH_PASSES: usize = 4;
+/// Spiral radius used to initialize the nearest-neighbor field.
+const NNF_INIT_SEARCH: isize = 24;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Diffusion,
+    PatchMatch,
+    Auto,
+}
+
+impl Method {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "diffusion" => Method::Diffusion,
+            "patchmatch" => Method::PatchMatch,
+            _ => Method::Auto,
+        }
+    }
+}
+
+/// Fill the pixels where `hole` is true (object, blemish, watermark) from their
+/// surroundings. All four channels are inpainted.
+///
+/// Diffusion (Telea, "An Image Inpainting Technique Based on the Fast
+/// Marching Method", 2004): hole pixels are filled in order of their exact
+/// Euclidean distance to the known region (onion peeling, distance.rs stands in
+/// for fast marching). Each pixel is a weighted first-order extrapolation
+/// I(q) + ∇I(q)·(p − q) from filled pixels q within `radius` (clamped to
+/// their value range), weighted by
+///   dir = |cos| between p − q and the boundary normal ∇T,
+///   dst = 1 / |p − q|²,   lev = 1 / (1 + |T(q) − T(p)|)
+/// so isophotes arriving at the boundary continue into the hole. Fast and
+/// ideal for thin regions (scratches, text, wires).
+///
+/// PatchMatch (Barnes et al., 2009) with Wexler-style EM voting for larger
+/// areas: starting from the diffusion fill, every hole pixel's patch is
+/// matched to a fully known source patch (nearest-neighbor field initialized
+/// by the shared matting spiral search, then improved by propagation and
+/// random search), and each hole pixel becomes the average of the source
+/// pixels that overlapping patches vote for. Repeating matching and voting
+/// lets texture replace the smooth diffusion guess.
+///
+/// "auto" uses diffusion when the hole is at most `AUTO_THIN_RADIUS` pixels
+/// from its boundary everywhere, PatchMatch otherwise.
+#[allow(clippy::too_many_arguments)]
+pub fn inpaint(
+    rgba: &[u8],
+    hole: &[bool],
+    w: usize,
+    h: usize,
+    method: Method,
+    radius: usize,
+    patch_size: usize,
+    iterations: usize,
+) -> Vec<u8> {
+    let known: Vec<bool> = hole.iter().map(|&v| !v).collect();
+    if known.iter().all(|&k| k) || known.iter().all(|&k| !k) {
+        return rgba.to_vec();
+    }
+
+    let mut img: Vec<[f32; 4]> = rgba
+        .chunks_exact(4)
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32, p[3] as f32])
+        .collect();
+
+    let dist = euclidean_distance(&known, w, h);
+    telea(&mut img, &known, &dist, w, h, radius.max(1));
+
+    let thickest = hole
+        .iter()
+        .zip(&dist)
+        .filter(|(&is_hole, _)| is_hole)
+        .map(|(_, &d)| d)
+        .fold(0.0f32, f32::max);
+    let use_patches = match method {
+        Method::Diffusion => false,
+        Method::PatchMatch => true,
+        Method::Auto => thickest > AUTO_THIN_RADIUS,
+    };
+    if use_patches {
+        patch_match(&mut img, hole, w, h, patch_size.max(3), iterations.max(1));
+    }
+
+    let mut out = rgba.to_vec();
+    for (i, px) in img.iter().enumerate() {
+        if hole[i] {
+            for c in 0..4 {
+                out[i * 4 + c] = (px[c] + 0.5).clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    out
+}
+
+/// Telea fill in order of distance `t` to the known region.
+fn telea(img: &mut [[f32; 4]], known: &[bool], t: &[f32], w: usize, h: usize, radius: usize) {
+    let mut order: Vec<usize> = (0..w * h).filter(|&i| !known[i]).collect();
+    order.sort_by(|&a, &b| t[a].total_cmp(&t[b]));
+
+    let mut filled = known.to_vec();
+    let r = radius as isize;
+    let r2 = r * r;
+
+    for &p in &order {
+        let px = (p % w) as isize;
+        let py = (p / w) as isize;
+
+        // Boundary normal from the distance field
+        let tx = |x: isize| t[py as usize * w + x.clamp(0, w as isize - 1) as usize];
+        let ty = |y: isize| t[y.clamp(0, h as isize - 1) as usize * w + px as usize];
+        let mut nx = tx(px + 1) - tx(px - 1);
+        let mut ny = ty(py + 1) - ty(py - 1);
+        let nlen = (nx * nx + ny * ny).sqrt();
+        if nlen > 0.0 {
+            nx /= nlen;
+            ny /= nlen;
+        }
+
+        let mut acc = [0.0f32; 4];
+        let mut wsum = 0.0f32;
+        let mut lo = [f32::MAX; 4];
+        let mut hi = [f32::MIN; 4];
+        for dy in -r..=r {
+            let qy = py + dy;
+            if qy < 0 || qy >= h as isize {
+                continue;
+            }
+            for dx in -r..=r {
+                let d2 = dx * dx + dy * dy;
+                let qx = px + dx;
+                if d2 == 0 || d2 > r2 || qx < 0 || qx >= w as isize {
+                    continue;
+                }
+                let q = qy as usize * w + qx as usize;
+                if !filled[q] {
+                    continue;
+                }
+
+                // r = p − q
+                let rx = -dx as f32;
+                let ry = -dy as f32;
+                let len = (d2 as f32).sqrt();
+                let dir = if nlen > 0.0 {
+                    ((rx * nx + ry * ny) / len).abs().max(0.01)
+                } else {
+                    1.0
+                };
+                let dst = 1.0 / d2 as f32;
+                let lev = 1.0 / (1.0 + (t[q] - t[p]).abs());
+                let wt = dir * dst * lev;
+
+                let [gx, gy] = gradient(img, &filled, w, h, qx as usize, qy as usize);
+                for c in 0..4 {
+                    acc[c] += wt * (img[q][c] + gx[c] * rx + gy[c] * ry);
+                    lo[c] = lo[c].min(img[q][c]);
+                    hi[c] = hi[c].max(img[q][c]);
+                }
+                wsum += wt;
+            }
+        }
+
+        // Clamping to the neighbors' range stops the linear extrapolation
+        // from running away across strong edges deep inside the hole
+        if wsum > 0.0 {
+            for c in 0..4 {
+                img[p][c] = (acc[c] / wsum).clamp(lo[c], hi[c]);
+            }
+        }
+        filled[p] = true;
+    }
+}
+
+/// Per-channel image gradient at (x, y) using filled neighbors only.
+fn gradient(
+    img: &[[f32; 4]],
+    filled: &[bool],
+    w: usize,
+    h: usize,
+    x: usize,
+    y: usize,
+) -> [[f32; 4]; 2] {
+    let i = y * w + x;
+    let axis = |prev: Option<usize>, next: Option<usize>| -> [f32; 4] {
+        let prev = prev.filter(|&j| filled[j]);
+        let next = next.filter(|&j| filled[j]);
+        let mut g = [0.0f32; 4];
+        for (c, gc) in g.iter_mut().enumerate() {
+            *gc = match (prev, next) {
+                (Some(a), Some(b)) => (img[b][c] - img[a][c]) * 0.5,
+                (None, Some(b)) => img[b][c] - img[i][c],
+                (Some(a), None) => img[i][c] - img[a][c],
+                (None, None) => 0.0,
+            };
+        }
+        g
+    };
+    [
+        axis((x > 0).then(|| i - 1), (x + 1 < w).then_some(i + 1)),
+        axis((y > 0).then(|| i - w), (y + 1 < h).then_some(i + w)),
+    ]
+}
+
+/// PatchMatch nearest-neighbor search + EM voting over the hole.
+fn patch_match(
+    img: &mut [[f32; 4]],
+    hole: &[bool],
+    w: usize,
+    h: usize,
+    patch_size: usize,
+    iterations: usize,
+) {
+    let r = (patch_size / 2) as isize;
+    if w as isize <= 2 * r || h as isize <= 2 * r {
+        return;
+    }
+
+    // Valid source centers: whole patch inside the image and hole-free
+    let iw = w + 1;
+    let mut integral = vec![0u32; iw * (h + 1)];
+    for y in 0..h {
+        let mut row = 0u32;
+        for x in 0..w {
+            row += hole[y * w + x] as u32;
+            integral[(y + 1) * iw + x + 1] = integral[y * iw + x + 1] + row;
+        }
+    }
+    let ru = r as usize;
+    let mut valid = vec![false; w * h];
+    let mut sources = Vec::new();
+    for y in ru..h - ru {
+        for x in ru..w - ru {
+            let (x0, y0, x1, y1) = (x - ru, y - ru, x + ru + 1, y + ru + 1);
+            let holes = integral[y1 * iw + x1] + integral[y0 * iw + x0]
+                - integral[y0 * iw + x1]
+                - integral[y1 * iw + x0];
+            if holes == 0 {
+                valid[y * w + x] = true;
+                sources.push(y * w + x);
+            }
+        }
+    }
+    if sources.is_empty() {
+        return;
+    }
+
+    let targets: Vec<usize> = (0..w * h).filter(|&i| hole[i]).collect();
+    let mut rng = XorShift(0x9E37_79B9);
+
+    // NNF init: nearest valid source center along the spiral
+    let spiral = spiral_offsets(NNF_INIT_SEARCH);
+    let mut nnf = vec![0usize; w * h];
+    for &c in &targets {
+        let cx = (c % w) as isize;
+        let cy = (c / w) as isize;
+        nnf[c] = spiral
+            .iter()
+            .map(|&(dx, dy, _)| (cx + dx, cy + dy))
+            .find(|&(x, y)| {
+                x >= 0
+                    && y >= 0
+                    && x < w as isize
+                    && y < h as isize
+                    && valid[y as usize * w + x as usize]
+            })
+            .map_or_else(
+                || sources[rng.below(sources.len())],
+                |(x, y)| y as usize * w + x as usize,
+            );
+    }
+
+    let mut cost = vec![0.0f32; w * h];
+    for _ in 0..iterations {
+        for &c in &targets {
+            cost[c] = patch_distance(img, w, h, r, c, nnf[c], f32::MAX);
+        }
+
+        for pass in 0..PM_SEARCH_PASSES {
+            let forward = pass % 2 == 0;
+            let step: isize = if forward { -1 } else { 1 };
+            for k in 0..targets.len() {
+                let c = targets[if forward { k } else { targets.len() - 1 - k }];
+                let cx = (c % w) as isize;
+                let cy = (c / w) as isize;
+
+                let mut best = nnf[c];
+                let mut best_cost = cost[c];
+                let try_candidate = |s: usize, best: &mut usize, best_cost: &mut f32| {
+                    if !valid[s] || s == *best {
+                        return;
+                    }
+                    let d = patch_distance(img, w, h, r, c, s, *best_cost);
+                    if d < *best_cost {
+                        *best_cost = d;
+                        *best = s;
+                    }
+                };
+
+                // Propagation from the already-visited neighbors, shifted
+                for (nx, ny) in [(cx + step, cy), (cx, cy + step)] {
+                    if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+                        continue;
+                    }
+                    let n = ny as usize * w + nx as usize;
+                    if !hole[n] {
+                        continue;
+                    }
+                    let sx = (nnf[n] % w) as isize - (nx - cx);
+                    let sy = (nnf[n] / w) as isize - (ny - cy);
+                    if sx >= 0 && sy >= 0 && sx < w as isize && sy < h as isize {
+                        try_candidate(sy as usize * w + sx as usize, &mut best, &mut best_cost);
+                    }
+                }
+
+                // Random search in exponentially shrinking windows
+                let mut radius = w.max(h) as isize;
+                while radius >= 1 {
+                    let bx = (best % w) as isize;
+                    let by = (best / w) as isize;
+                    let sx = bx + rng.range(radius);
+                    let sy = by + rng.range(radius);
+                    if sx >= 0 && sy >= 0 && sx < w as isize && sy < h as isize {
+                        try_candidate(sy as usize * w + sx as usize, &mut best, &mut best_cost);
+                    }
+                    radius /= 2;
+                }
+
+                nnf[c] = best;
+                cost[c] = best_cost;
+            }
+        }
+
+        // Vote: each hole pixel averages what overlapping hole patches map it to
+        let mut next = img.to_vec();
+        for &p in &targets {
+            let px = (p % w) as isize;
+            let py = (p / w) as isize;
+            let mut acc = [0.0f32; 4];
+            let mut n = 0.0f32;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let cx = px + dx;
+                    let cy = py + dy;
+                    if cx < 0 || cy < 0 || cx >= w as isize || cy >= h as isize {
+                        continue;
+                    }
+                    let c = cy as usize * w + cx as usize;
+                    if !hole[c] {
+                        continue;
+                    }
+                    // p sits at offset (−dx, −dy) from patch center c
+                    let s = nnf[c];
+                    let sx = (s % w) as isize - dx;
+                    let sy = (s / w) as isize - dy;
+                    let v = img[sy as usize * w + sx as usize];
+                    for ch in 0..4 {
+                        acc[ch] += v[ch];
+                    }
+                    n += 1.0;
+                }
+            }
+            if n > 0.0 {
+                for ch in 0..4 {
+                    next[p][ch] = acc[ch] / n;
+                }
+            }
+        }
+        img.copy_from_slice(&next);
+    }
+}
+
+/// SSD between the patch at target center `c` and source center `s`.
+///
+/// Target offsets outside the image are skipped; stops early past `limit`.
+fn patch_distance(
+    img: &[[f32; 4]],
+    w: usize,
+    h: usize,
+    r: isize,
+    c: usize,
+    s: usize,
+    limit: f32,
+) -> f32 {
+    let cx = (c % w) as isize;
+    let cy = (c / w) as isize;
+    let sx = (s % w) as isize;
+    let sy = (s / w) as isize;
+    let mut sum = 0.0f32;
+    for dy in -r..=r {
+        let ty = cy + dy;
+        if ty < 0 || ty >= h as isize {
+            continue;
+        }
+        for dx in -r..=r {
+            let tx = cx + dx;
+            if tx < 0 || tx >= w as isize {
+                continue;
+            }
+            let a = img[ty as usize * w + tx as usize];
+            let b = img[(sy + dy) as usize * w + (sx + dx) as usize];
+            for ch in 0..4 {
+                let d = a[ch] - b[ch];
+                sum += d * d;
+            }
+        }
+        if sum >= limit {
+            return sum;
+        }
+    }
+    sum
+}
+
+/// Small deterministic PRNG (xorshift32) for the random search.
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform in [0, n).
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() as usize) % n
+    }
+
+    /// Uniform in [−r, r].
+    fn range(&mut self, r: isize) -> isize {
+        self.below((2 * r + 1) as usize) as isize - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 32×32 image of `color` with a black 10×10 square hole at (11, 11).
+    fn holed(color: [u8; 4]) -> (Vec<u8>, Vec<bool>) {
+        let mut rgba = color.repeat(32 * 32);
+        let mut hole = vec![false; 32 * 32];
+        for y in 11..21 {
+            for x in 11..21 {
+                let i = y * 32 + x;
+                hole[i] = true;
+                rgba[i * 4..i * 4 + 4].copy_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+        (rgba, hole)
+    }
+
+    #[test]
+    fn test_hole_is_filled_from_its_surroundings() {
+        let color = [40, 160, 90, 255];
+        for method in [Method::Diffusion, Method::PatchMatch, Method::Auto] {
+            let (rgba, hole) = holed(color);
+            let out = inpaint(&rgba, &hole, 32, 32, method, 3, 7, 4);
+            for (i, px) in out.chunks_exact(4).enumerate() {
+                assert_eq!(px, &color, "pixel {i} (hole: {})", hole[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_pixels_untouched() {
+        // Horizontal ramp: only the hole may change, and it follows the ramp
+        let mut rgba: Vec<u8> = (0..32 * 32)
+            .flat_map(|i| [(i % 32 * 8) as u8, 100, 0, 255])
+            .collect();
+        let (_, hole) = holed([0; 4]);
+        for (px, &is_hole) in rgba.chunks_exact_mut(4).zip(&hole) {
+            if is_hole {
+                px[0] = 255;
+            }
+        }
+        let out = inpaint(&rgba, &hole, 32, 32, Method::Diffusion, 3, 7, 4);
+        for (i, (a, b)) in out.chunks_exact(4).zip(rgba.chunks_exact(4)).enumerate() {
+            if hole[i] {
+                let x = (i % 32) as i32;
+                assert!((a[0] as i32 - x * 8).abs() <= 48, "x {x}: {}", a[0]);
+                assert_eq!(a[1], 100);
+            } else {
+                assert_eq!(a, b);
+            }
+        }
+    }
+}
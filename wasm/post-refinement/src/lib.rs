@@ -1,10 +1,34 @@
+mod autocrop;
 mod blur;
+mod bokeh;
+mod chroma_key;
+mod compositing;
+mod contour;
+mod dehaze;
+mod distance;
 mod edge_refine;
 mod fast_guided_filter;
+mod inpaint;
+mod magic_wand;
+mod masks;
+mod metrics;
+mod options;
+mod outline;
 mod poisson;
+mod seamless_clone;
+mod shadows_highlights;
 mod shared_matting;
+mod temporal;
+mod tone;
 mod trimap;
+mod vectors;
 
+pub use compositing::{auto_crop, inpaint, portrait_blur, seamless_clone, sticker_outline};
+pub use masks::{chroma_key, magic_wand_mask, mask_metrics, post_process_sequence};
+pub use tone::{dehaze, shadows_highlights};
+pub use vectors::{mask_to_contours, mask_to_svg_path};
+
+use options::PostProcessOptions;
 use wasm_bindgen::prelude::*;
 
 /// High-performance post-refinement pipeline.
@@ -16,7 +40,7 @@ use wasm_bindgen::prelude::*;
 /// - Guided Filter: Subsampled (s=4) with integral images (O(1) box mean)
 /// - Shared Matting: Spiral search with early termination + multi-sample confidence
 /// - Edge Refine: Scharr operator (better isotropy than Sobel, same cost)
-/// - Poisson: SOR with ω=1.5 (2x faster convergence than Gauss-Seidel)
+/// - Poisson: Multigrid V-cycles until the residual converges (resolution-independent)
 /// - Feather: Separable running-sum box blur
 #[wasm_bindgen]
 #[allow(clippy::too_many_arguments)]
@@ -29,6 +53,45 @@ pub fn post_process(
     guide_eps: f32,
     edge_threshold: u32,
     feather_radius: u32,
+) -> Vec<u8> {
+    let options =
+        PostProcessOptions::with_guide(guide_radius, guide_eps, edge_threshold, feather_radius);
+    refine_rgba(mask_rgba, original_rgba, width, height, &options)
+}
+
+/// `post_process` with every setting in one object.
+///
+/// `options` is the worker's `PostProcessingConfig` object, passed as is; see
+/// `PostProcessOptions` for the keys and their defaults. On top of the
+/// `post_process` arguments it sets the Poisson stage: `poissonMethod`
+/// ("multigrid" or "sor"), `poissonTolerance` and `poissonMaxIterations`.
+///
+/// Errors if `options` is not an object of the expected shape.
+#[wasm_bindgen]
+pub fn post_process_with_options(
+    mask_rgba: &[u8],
+    original_rgba: &[u8],
+    width: u32,
+    height: u32,
+    options: JsValue,
+) -> Result<Vec<u8>, JsError> {
+    let options: PostProcessOptions = serde_wasm_bindgen::from_value(options)?;
+    Ok(refine_rgba(
+        mask_rgba,
+        original_rgba,
+        width,
+        height,
+        &options,
+    ))
+}
+
+/// Refine the alpha of `mask_rgba` and put it under the original's RGB.
+fn refine_rgba(
+    mask_rgba: &[u8],
+    original_rgba: &[u8],
+    width: u32,
+    height: u32,
+    options: &PostProcessOptions,
 ) -> Vec<u8> {
     let w = width as usize;
     let h = height as usize;
@@ -39,31 +102,52 @@ pub fn post_process(
         return mask_rgba.to_vec();
     }
 
-    // === Fused extraction: alpha + guidance in one pass ===
-    let mut alpha = vec![0.0f32; npx];
-    let mut guide = vec![0.0f32; npx];
     let inv255 = 1.0 / 255.0;
+    let alpha: Vec<f32> = mask_rgba
+        .chunks_exact(4)
+        .map(|p| p[3] as f32 * inv255)
+        .collect();
+
+    let refined = refine_alpha(&alpha, original_rgba, w, h, options);
+
+    // === Compose output ===
+    // Use the ORIGINAL image pixel data for RGB channels to preserve quality
+    // Use the REFINED alpha channel for the mask
+    let mut output = original_rgba.to_vec();
     for i in 0..npx {
-        let off = i * 4;
-        alpha[i] = mask_rgba[off + 3] as f32 * inv255;
-        guide[i] = (original_rgba[off] as f32 * 0.2126
-            + original_rgba[off + 1] as f32 * 0.7152
-            + original_rgba[off + 2] as f32 * 0.0722)
-            * inv255;
+        output[i * 4 + 3] = (refined[i] * 255.0).clamp(0.0, 255.0) as u8;
     }
 
+    output
+}
+
+/// Steps 1–6 of `post_process`: refine a coarse alpha against the original.
+fn refine_alpha(
+    alpha: &[f32],
+    original_rgba: &[u8],
+    w: usize,
+    h: usize,
+    options: &PostProcessOptions,
+) -> Vec<f32> {
+    // === Guidance: luminance of the original ===
+    let inv255 = 1.0 / 255.0;
+    let guide: Vec<f32> = original_rgba
+        .chunks_exact(4)
+        .map(|p| (p[0] as f32 * 0.2126 + p[1] as f32 * 0.7152 + p[2] as f32 * 0.0722) * inv255)
+        .collect();
+
     // === Step 1: Trimap via BFS distance transform (O(n)) ===
-    let trimap = trimap::generate_trimap_bfs(&alpha, w, h, 5);
+    let trimap = trimap::generate_trimap_bfs(alpha, w, h, 5);
 
     // === Step 2: Fast Guided Filter (subsampled) ===
     let subsample = 4usize.min(w.min(h) / 8).max(1);
     let mut refined = fast_guided_filter::fast_guided_filter(
         &guide,
-        &alpha,
+        alpha,
         w,
         h,
-        guide_radius as usize,
-        guide_eps,
+        options.guide_radius as usize,
+        options.guide_eps,
         subsample,
     );
 
@@ -71,24 +155,39 @@ pub fn post_process(
     shared_matting::shared_matting(&mut refined, original_rgba, &trimap, w, h);
 
     // === Step 4: Edge refinement with Scharr operator ===
-    let edge_thresh = edge_threshold as f32 / 255.0;
+    let edge_thresh = options.edge_threshold as f32 / 255.0;
     edge_refine::refine_edges_scharr(&mut refined, &guide, w, h, edge_thresh);
 
-    // === Step 5: Poisson gradient smoothing (SOR) ===
-    poisson::poisson_sor(&mut refined, &guide, w, h, 3);
+    // === Step 5: Poisson gradient smoothing (residual-controlled) ===
+    let multigrid = options.poisson_method != "sor";
+    let max_iterations = match (options.poisson_max_iterations, multigrid) {
+        (0, true) => poisson::DEFAULT_MAX_CYCLES,
+        (0, false) => poisson::DEFAULT_MAX_SWEEPS,
+        (n, _) => n as usize,
+    };
+    poisson::poisson_smooth(
+        &mut refined,
+        &guide,
+        w,
+        h,
+        options.poisson_tolerance,
+        max_iterations,
+        multigrid,
+    );
 
     // === Step 6: Feathering ===
-    if feather_radius > 0 {
-        refined = blur::box_blur_separable(&refined, w, h, feather_radius as usize);
+    if options.feather_radius > 0 {
+        refined = blur::box_blur_separable(&refined, w, h, options.feather_radius as usize);
     }
 
-    // === Compose output ===
-    // Use the ORIGINAL image pixel data for RGB channels to preserve quality
-    // Use the REFINED alpha channel for the mask
-    let mut output = original_rgba.to_vec();
-    for i in 0..npx {
-        output[i * 4 + 3] = (refined[i] * 255.0).clamp(0.0, 255.0) as u8;
-    }
+    refined
+}
 
-    output
+/// Prefix RGBA pixels with little-endian u32 width and height.
+fn pack_with_dims(rgba: &[u8], w: usize, h: usize) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(8 + rgba.len());
+    packed.extend_from_slice(&(w as u32).to_le_bytes());
+    packed.extend_from_slice(&(h as u32).to_le_bytes());
+    packed.extend_from_slice(rgba);
+    packed
 }
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+
+/// Color-distance width of the anti-aliasing falloff beyond the tolerance.
+const AA_BAND: f32 = 48.0;
+
+/// Magic-wand selection: coverage (0–1) flood-filled by color similarity from
+/// `seeds` (pixel coordinates; seeds outside the image are ignored).
+///
+/// A pixel matches a seed when its RGB Euclidean distance to the seed's color
+/// is within `tolerance` (0–441). Comparing against the seed color rather than
+/// the neighbor avoids drifting along gradients.
+///
+/// - Contiguous: 4-connected BFS from each seed, like the paint-bucket tool
+/// - Global: every matching pixel in the image, connected or not
+///
+/// Anti-aliasing gives unselected pixels bordering the selection a partial
+/// coverage that falls off linearly over `AA_BAND` of color distance past the
+/// tolerance, so blended edge pixels of flat graphics get fractional alpha.
+pub fn select(
+    rgba: &[u8],
+    w: usize,
+    h: usize,
+    seeds: &[(usize, usize)],
+    tolerance: f32,
+    contiguous: bool,
+    antialias: bool,
+) -> Vec<f32> {
+    let npx = w * h;
+    let mut sel = vec![0.0f32; npx];
+    let seeds: Vec<(usize, usize)> = seeds
+        .iter()
+        .copied()
+        .filter(|&(x, y)| x < w && y < h)
+        .collect();
+    let colors: Vec<[f32; 3]> = seeds.iter().map(|&(x, y)| pixel(rgba, y * w + x)).collect();
+    let tol2 = tolerance.max(0.0).powi(2);
+    let matches = |i: usize, c: &[f32; 3]| dist2(pixel(rgba, i), c) <= tol2;
+
+    if contiguous {
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for (&(x, y), color) in seeds.iter().zip(&colors) {
+            let start = y * w + x;
+            if sel[start] > 0.0 {
+                continue;
+            }
+            sel[start] = 1.0;
+            queue.push_back(start);
+            while let Some(idx) = queue.pop_front() {
+                let x = idx % w;
+                let y = idx / w;
+                let mut visit = |ni: usize| {
+                    if sel[ni] == 0.0 && matches(ni, color) {
+                        sel[ni] = 1.0;
+                        queue.push_back(ni);
+                    }
+                };
+                if x > 0 {
+                    visit(idx - 1);
+                }
+                if x + 1 < w {
+                    visit(idx + 1);
+                }
+                if y > 0 {
+                    visit(idx - w);
+                }
+                if y + 1 < h {
+                    visit(idx + w);
+                }
+            }
+        }
+    } else {
+        for (i, s) in sel.iter_mut().enumerate() {
+            if colors.iter().any(|c| matches(i, c)) {
+                *s = 1.0;
+            }
+        }
+    }
+
+    if antialias && !colors.is_empty() {
+        let tol = tolerance.max(0.0);
+        let mut soft = sel.clone();
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                if sel[i] > 0.0 || !borders_selection(&sel, w, h, x, y) {
+                    continue;
+                }
+                let px = pixel(rgba, i);
+                let d = colors
+                    .iter()
+                    .map(|c| dist2(px, c))
+                    .fold(f32::MAX, f32::min)
+                    .sqrt();
+                soft[i] = (1.0 - (d - tol) / AA_BAND).clamp(0.0, 1.0);
+            }
+        }
+        sel = soft;
+    }
+
+    sel
+}
+
+/// Whether any 8-neighbor of (x, y) is fully selected.
+fn borders_selection(sel: &[f32], w: usize, h: usize, x: usize, y: usize) -> bool {
+    let y0 = y.saturating_sub(1);
+    let y1 = (y + 1).min(h - 1);
+    let x0 = x.saturating_sub(1);
+    let x1 = (x + 1).min(w - 1);
+    (y0..=y1).any(|ny| (x0..=x1).any(|nx| sel[ny * w + nx] >= 1.0))
+}
+
+#[inline]
+fn pixel(rgba: &[u8], i: usize) -> [f32; 3] {
+    [
+        rgba[i * 4] as f32,
+        rgba[i * 4 + 1] as f32,
+        rgba[i * 4 + 2] as f32,
+    ]
+}
+
+#[inline]
+fn dist2(a: [f32; 3], b: &[f32; 3]) -> f32 {
+    let dr = a[0] - b[0];
+    let dg = a[1] - b[1];
+    let db = a[2] - b[2];
+    dr * dr + dg * dg + db * db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8×4 image: a left and a right block of `a`, split by a column of `wall`.
+    fn two_blocks(a: [u8; 3], wall: [u8; 3]) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(8 * 4 * 4);
+        for _ in 0..4 {
+            for x in 0..8 {
+                let c = if x == 4 { wall } else { a };
+                rgba.extend_from_slice(&[c[0], c[1], c[2], 255]);
+            }
+        }
+        rgba
+    }
+
+    fn count(sel: &[f32]) -> usize {
+        sel.iter().filter(|&&s| s >= 1.0).count()
+    }
+
+    #[test]
+    fn test_tolerance_grows_selection() {
+        // Horizontal ramp in steps of 10
+        let rgba: Vec<u8> = (0..10u8).flat_map(|x| [x * 10, 0, 0, 255]).collect();
+        let sizes: Vec<usize> = [0.0, 10.0, 25.0, 100.0]
+            .iter()
+            .map(|&t| count(&select(&rgba, 10, 1, &[(0, 0)], t, true, false)))
+            .collect();
+        assert_eq!(sizes, vec![1, 2, 3, 10]);
+    }
+
+    #[test]
+    fn test_contiguous_stops_at_other_colors() {
+        let rgba = two_blocks([200, 40, 40], [0, 0, 255]);
+        let sel = select(&rgba, 8, 4, &[(0, 0)], 30.0, true, false);
+        assert_eq!(count(&sel), 4 * 4);
+        assert_eq!(sel[5], 0.0);
+
+        let sel = select(&rgba, 8, 4, &[(0, 0)], 30.0, false, false);
+        assert_eq!(count(&sel), 7 * 4);
+        assert_eq!(sel[4], 0.0);
+    }
+
+    #[test]
+    fn test_antialias_softens_border() {
+        // The wall is close enough to partly join the selection
+        let rgba = two_blocks([200, 40, 40], [200, 40, 90]);
+        let sel = select(&rgba, 8, 4, &[(0, 0)], 30.0, true, true);
+        assert!(sel[4] > 0.0 && sel[4] < 1.0, "{}", sel[4]);
+        assert_eq!(sel[5], 0.0);
+    }
+
+    #[test]
+    fn test_seed_outside_image_is_ignored() {
+        let rgba = two_blocks([200, 40, 40], [0, 0, 255]);
+        let sel = select(&rgba, 8, 4, &[(8, 0), (0, 4), (99, 99)], 30.0, true, true);
+        assert!(sel.iter().all(|&s| s == 0.0));
+        let sel = select(&rgba, 8, 4, &[(99, 0), (7, 3)], 30.0, true, false);
+        assert_eq!(count(&sel), 3 * 4);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::options::PostProcessOptions;
+use crate::{magic_wand, metrics, refine_alpha, temporal};
+use wasm_bindgen::prelude::*;
+
+/// Chroma-key (green/blue screen) background removal without the ML model.
+///
+/// The key color is `key_r/g/b`, or detected from the image border when
+/// `auto_key` is set. Alpha ramps from 0 at `tolerance` to 1 at
+/// `tolerance + softness` (chroma distance, 0–255 units) and then goes through
+/// the same refinement as `post_process`. `spill` (0–1) removes the key's
+/// color cast from the foreground. Returns RGBA with the keyed alpha.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn chroma_key(
+    original_rgba: &[u8],
+    width: u32,
+    height: u32,
+    key_r: u8,
+    key_g: u8,
+    key_b: u8,
+    auto_key: bool,
+    tolerance: f32,
+    softness: f32,
+    spill: f32,
+    guide_radius: u32,
+    guide_eps: f32,
+    edge_threshold: u32,
+    feather_radius: u32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if original_rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return original_rgba.to_vec();
+    }
+
+    let key = if auto_key {
+        crate::chroma_key::detect_key_color(original_rgba, w, h)
+    } else {
+        [key_r, key_g, key_b]
+    };
+    let (alpha, mut output) =
+        crate::chroma_key::chroma_key(original_rgba, key, tolerance, softness, spill);
+
+    let options =
+        PostProcessOptions::with_guide(guide_radius, guide_eps, edge_threshold, feather_radius);
+    let refined = refine_alpha(&alpha, original_rgba, w, h, &options);
+    for (i, &a) in refined.iter().enumerate() {
+        output[i * 4 + 3] = (a * 255.0).clamp(0.0, 255.0) as u8;
+    }
+
+    output
+}
+
+/// Temporally consistent refinement of a frame sequence (GIFs, bursts).
+///
+/// `masks_rgba` and `frames_rgba` hold `frame_count` consecutive RGBA frames
+/// back to back. Each frame is refined like `post_process`, then alpha is
+/// smoothed over time on static pixels: `temporal_strength` (0–1) sets how
+/// much of the neighboring frames' alpha is kept, and luminance changes above
+/// `motion_threshold` (0–255) switch smoothing off so moving edges follow the
+/// motion. Returns the frames back to back with refined alpha; invalid input
+/// returns the masks copy.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn post_process_sequence(
+    masks_rgba: &[u8],
+    frames_rgba: &[u8],
+    width: u32,
+    height: u32,
+    frame_count: u32,
+    guide_radius: u32,
+    guide_eps: f32,
+    edge_threshold: u32,
+    feather_radius: u32,
+    temporal_strength: f32,
+    motion_threshold: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    let frame_len = w * h * 4;
+    let expected = frame_len * frame_count as usize;
+    if masks_rgba.len() != expected || frames_rgba.len() != expected || frame_len == 0 {
+        return masks_rgba.to_vec();
+    }
+
+    let options =
+        PostProcessOptions::with_guide(guide_radius, guide_eps, edge_threshold, feather_radius);
+    let inv255 = 1.0 / 255.0;
+    let mut alphas = Vec::with_capacity(frame_count as usize);
+    let mut lumas = Vec::with_capacity(frame_count as usize);
+    for (mask, frame) in masks_rgba
+        .chunks_exact(frame_len)
+        .zip(frames_rgba.chunks_exact(frame_len))
+    {
+        let alpha: Vec<f32> = mask.chunks_exact(4).map(|p| p[3] as f32 * inv255).collect();
+        alphas.push(refine_alpha(&alpha, frame, w, h, &options));
+        lumas.push(
+            frame
+                .chunks_exact(4)
+                .map(|p| {
+                    (p[0] as f32 * 0.2126 + p[1] as f32 * 0.7152 + p[2] as f32 * 0.0722) * inv255
+                })
+                .collect::<Vec<f32>>(),
+        );
+    }
+
+    temporal::stabilize(
+        &mut alphas,
+        &lumas,
+        w,
+        h,
+        temporal_strength,
+        motion_threshold * inv255,
+    );
+
+    let mut output = frames_rgba.to_vec();
+    for (frame, alpha) in output.chunks_exact_mut(frame_len).zip(&alphas) {
+        for (px, &a) in frame.chunks_exact_mut(4).zip(alpha) {
+            px[3] = (a * 255.0).clamp(0.0, 255.0) as u8;
+        }
+    }
+    output
+}
+
+/// Magic-wand mask: flood-fill selection from clicked seed points.
+///
+/// `seeds` holds `[x0, y0, x1, y1, …]` pixel coordinates. Pixels within
+/// `tolerance` (RGB distance, 0–441) of a seed's color are selected — only
+/// those connected to the seed when `contiguous`, otherwise anywhere. With
+/// `antialias` the selection edge gets fractional coverage.
+///
+/// The selection is removed (alpha 0) unless `invert`, which keeps only the
+/// selection. Returns the original RGB with that alpha, ready for
+/// `post_process` as `mask_rgba`; invalid input returns the input copy.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn magic_wand_mask(
+    original_rgba: &[u8],
+    width: u32,
+    height: u32,
+    seeds: &[u32],
+    tolerance: f32,
+    contiguous: bool,
+    antialias: bool,
+    invert: bool,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if original_rgba.len() != w * h * 4 || !seeds.len().is_multiple_of(2) {
+        return original_rgba.to_vec();
+    }
+
+    let points: Vec<(usize, usize)> = seeds
+        .chunks_exact(2)
+        .map(|p| (p[0] as usize, p[1] as usize))
+        .collect();
+    let sel = magic_wand::select(
+        original_rgba,
+        w,
+        h,
+        &points,
+        tolerance,
+        contiguous,
+        antialias,
+    );
+
+    let mut output = original_rgba.to_vec();
+    for (i, &s) in sel.iter().enumerate() {
+        let keep = if invert { s } else { 1.0 - s };
+        output[i * 4 + 3] = (keep * 255.0 + 0.5) as u8;
+    }
+    output
+}
+
+/// Matting quality metrics of a predicted mask against ground truth.
+///
+/// Both masks are RGBA; only alpha is compared. With a non-empty
+/// `trimap_rgba` (R channel, 128 = unknown) the sums cover the unknown zone
+/// only. Returns `[sad, mse, gradient, connectivity, iou]`, or an empty Vec
+/// on invalid input.
+#[wasm_bindgen]
+pub fn mask_metrics(
+    predicted_rgba: &[u8],
+    truth_rgba: &[u8],
+    trimap_rgba: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    let w = width as usize;
+    let h = height as usize;
+    let expected = w * h * 4;
+    if predicted_rgba.len() != expected
+        || truth_rgba.len() != expected
+        || (!trimap_rgba.is_empty() && trimap_rgba.len() != expected)
+    {
+        return Vec::new();
+    }
+
+    let to_alpha =
+        |rgba: &[u8]| -> Vec<f32> { rgba.chunks_exact(4).map(|p| p[3] as f32 / 255.0).collect() };
+    let region: Option<Vec<bool>> = (!trimap_rgba.is_empty())
+        .then(|| trimap_rgba.chunks_exact(4).map(|p| p[0] == 128).collect());
+
+    let m = metrics::evaluate(
+        &to_alpha(predicted_rgba),
+        &to_alpha(truth_rgba),
+        w,
+        h,
+        region.as_deref(),
+    );
+    vec![m.sad, m.mse, m.gradient, m.connectivity, m.iou]
+}
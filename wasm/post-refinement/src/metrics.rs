@@ -0,0 +1,331 @@
+use std::collections::VecDeque;
+
+const GRADIENT_SIGMA: f32 = 1.4;
+const CONNECTIVITY_STEP: f32 = 0.1;
+const CONNECTIVITY_MIN_DELTA: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaskMetrics {
+    pub sad: f32,
+    pub mse: f32,
+    pub gradient: f32,
+    pub connectivity: f32,
+    pub iou: f32,
+}
+
+/// Quality metrics of the alpha matte `pred` against the ground truth `truth`,
+/// as used by the alphamatting.com benchmark (Rhemann et al., "A Perceptually
+/// Motivated Online Benchmark for Image Matting", CVPR 2009).
+///
+/// All alphas are in [0, 1]. With a `region` mask (typically the trimap's
+/// unknown zone) sums run over that region only.
+///
+/// - SAD: Σ |α − α*|
+/// - MSE: mean (α − α*)²
+/// - Gradient: Σ (|∇α| − |∇α*|)², first-order Gaussian derivatives, σ = 1.4
+/// - Connectivity: Σ |φ(α) − φ(α*)| with φ = 1 − d·[d ≥ 0.15], d = α − l, where
+///   l is the highest threshold (steps of 0.1) at which the pixel is still
+///   4-connected to the largest component where both mattes exceed it
+/// - IoU of the masks binarized at 0.5 (1 when both are empty)
+pub fn evaluate(
+    pred: &[f32],
+    truth: &[f32],
+    w: usize,
+    h: usize,
+    region: Option<&[bool]>,
+) -> MaskMetrics {
+    let counted = |i: usize| region.is_none_or(|r| r[i]);
+
+    let mut sad = 0.0f64;
+    let mut sq = 0.0f64;
+    let mut n = 0usize;
+    let mut inter = 0usize;
+    let mut union = 0usize;
+    for i in 0..w * h {
+        if !counted(i) {
+            continue;
+        }
+        let d = (pred[i] - truth[i]) as f64;
+        sad += d.abs();
+        sq += d * d;
+        n += 1;
+        let p = pred[i] >= 0.5;
+        let t = truth[i] >= 0.5;
+        inter += (p && t) as usize;
+        union += (p || t) as usize;
+    }
+
+    let gp = gradient_magnitude(pred, w, h);
+    let gt = gradient_magnitude(truth, w, h);
+    let gradient: f64 = (0..w * h)
+        .filter(|&i| counted(i))
+        .map(|i| ((gp[i] - gt[i]) as f64).powi(2))
+        .sum();
+
+    let levels = connectivity_levels(pred, truth, w, h);
+    let phi = |a: f32, l: f32| {
+        let d = a - l;
+        if d >= CONNECTIVITY_MIN_DELTA {
+            1.0 - d
+        } else {
+            1.0
+        }
+    };
+    let connectivity: f64 = (0..w * h)
+        .filter(|&i| counted(i))
+        .map(|i| (phi(pred[i], levels[i]) - phi(truth[i], levels[i])).abs() as f64)
+        .sum();
+
+    MaskMetrics {
+        sad: sad as f32,
+        mse: if n > 0 { (sq / n as f64) as f32 } else { 0.0 },
+        gradient: gradient as f32,
+        connectivity: connectivity as f32,
+        iou: if union > 0 {
+            inter as f32 / union as f32
+        } else {
+            1.0
+        },
+    }
+}
+
+/// |∇α| from separable Gaussian-derivative filters (clamped borders).
+fn gradient_magnitude(alpha: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let r = (3.0 * GRADIENT_SIGMA).ceil() as isize;
+    let s2 = GRADIENT_SIGMA * GRADIENT_SIGMA;
+    let g: Vec<f32> = (-r..=r)
+        .map(|x| (-(x * x) as f32 / (2.0 * s2)).exp())
+        .collect();
+    let g_sum: f32 = g.iter().sum();
+    let g: Vec<f32> = g.iter().map(|v| v / g_sum).collect();
+    // Derivative kernel scaled so a unit ramp has unit slope
+    let dg: Vec<f32> = (-r..=r)
+        .zip(&g)
+        .map(|(x, &gv)| -(x as f32) / s2 * gv)
+        .collect();
+    let slope: f32 = (-r..=r).zip(&dg).map(|(x, &d)| -(x as f32) * d).sum();
+    let dg: Vec<f32> = dg.iter().map(|v| v / slope).collect();
+
+    let gx = convolve(&convolve(alpha, w, h, &dg, true), w, h, &g, false);
+    let gy = convolve(&convolve(alpha, w, h, &g, true), w, h, &dg, false);
+    gx.iter()
+        .zip(&gy)
+        .map(|(a, b)| (a * a + b * b).sqrt())
+        .collect()
+}
+
+/// 1D convolution along rows (`horizontal`) or columns, clamped borders.
+fn convolve(src: &[f32], w: usize, h: usize, k: &[f32], horizontal: bool) -> Vec<f32> {
+    let r = (k.len() / 2) as isize;
+    let mut out = vec![0.0f32; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let mut acc = 0.0f32;
+            for (j, &kv) in k.iter().enumerate() {
+                // f(i − t) · k(t), t = j − r
+                let t = j as isize - r;
+                let (sx, sy) = if horizontal {
+                    ((x as isize - t).clamp(0, w as isize - 1) as usize, y)
+                } else {
+                    (x, (y as isize - t).clamp(0, h as isize - 1) as usize)
+                };
+                acc += src[sy * w + sx] * kv;
+            }
+            out[y * w + x] = acc;
+        }
+    }
+    out
+}
+
+/// Per-pixel level l: the last threshold before the pixel dropped out of
+/// the largest component of (pred ≥ λ) ∧ (truth ≥ λ); 1 if it never did.
+fn connectivity_levels(pred: &[f32], truth: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let npx = w * h;
+    let mut level = vec![-1.0f32; npx];
+    let steps = (1.0 / CONNECTIVITY_STEP).round() as usize;
+
+    let mut label = vec![0u32; npx];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for k in 1..=steps {
+        let lambda = k as f32 * CONNECTIVITY_STEP;
+        let prev = (k - 1) as f32 * CONNECTIVITY_STEP;
+
+        // Largest 4-connected component of the joint thresholded mask
+        label.iter_mut().for_each(|l| *l = 0);
+        let mut next_label = 0u32;
+        let mut best = (0u32, 0usize);
+        for start in 0..npx {
+            if label[start] != 0 || pred[start] < lambda || truth[start] < lambda {
+                continue;
+            }
+            next_label += 1;
+            label[start] = next_label;
+            queue.push_back(start);
+            let mut size = 0usize;
+            while let Some(idx) = queue.pop_front() {
+                size += 1;
+                let x = idx % w;
+                let y = idx / w;
+                let mut visit = |ni: usize| {
+                    if label[ni] == 0 && pred[ni] >= lambda && truth[ni] >= lambda {
+                        label[ni] = next_label;
+                        queue.push_back(ni);
+                    }
+                };
+                if x > 0 {
+                    visit(idx - 1);
+                }
+                if x + 1 < w {
+                    visit(idx + 1);
+                }
+                if y > 0 {
+                    visit(idx - w);
+                }
+                if y + 1 < h {
+                    visit(idx + w);
+                }
+            }
+            if size > best.1 {
+                best = (next_label, size);
+            }
+        }
+
+        for i in 0..npx {
+            if level[i] < 0.0 && (best.1 == 0 || label[i] != best.0) {
+                level[i] = prev;
+            }
+        }
+    }
+
+    for l in level.iter_mut() {
+        if *l < 0.0 {
+            *l = 1.0;
+        }
+    }
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// w × h matte of a soft-edged disc.
+    fn disc(w: usize, h: usize, cx: f32, cy: f32, r: f32) -> Vec<f32> {
+        let mut a = vec![0.0f32; w * h];
+        for y in 0..h {
+            for x in 0..w {
+                let d = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt();
+                a[y * w + x] = (r - d + 0.5).clamp(0.0, 1.0);
+            }
+        }
+        a
+    }
+
+    #[test]
+    fn test_identical_mattes() {
+        let a = disc(32, 32, 16.0, 16.0, 9.0);
+        let m = evaluate(&a, &a, 32, 32, None);
+        assert_eq!(m.sad, 0.0);
+        assert_eq!(m.mse, 0.0);
+        assert_eq!(m.gradient, 0.0);
+        assert_eq!(m.connectivity, 0.0);
+        assert_eq!(m.iou, 1.0);
+    }
+
+    #[test]
+    fn test_sad_mse_constant_offset() {
+        let a = vec![0.5f32; 100];
+        let b = vec![0.25f32; 100];
+        let m = evaluate(&a, &b, 10, 10, None);
+        assert!((m.sad - 25.0).abs() < 1e-4);
+        assert!((m.mse - 0.0625).abs() < 1e-6);
+        // Both flat: no gradient difference
+        assert!(m.gradient < 1e-6);
+    }
+
+    #[test]
+    fn test_iou_half_overlap() {
+        let (w, h) = (8, 4);
+        let mut a = vec![0.0f32; w * h];
+        let mut b = vec![0.0f32; w * h];
+        for y in 0..h {
+            for x in 0..4 {
+                a[y * w + x] = 1.0;
+            }
+            for x in 2..6 {
+                b[y * w + x] = 1.0;
+            }
+        }
+        let m = evaluate(&a, &b, w, h, None);
+        // 2 shared columns of 6 covered
+        assert!((m.iou - 2.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gradient_ramp_slope() {
+        let (w, h) = (40, 8);
+        let ramp: Vec<f32> = (0..w * h).map(|i| (i % w) as f32 / w as f32).collect();
+        let g = gradient_magnitude(&ramp, w, h);
+        assert!((g[4 * w + 20] - 1.0 / w as f32).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_connectivity_penalizes_islands() {
+        let (w, h) = (32, 32);
+        let truth = disc(w, h, 12.0, 16.0, 7.0);
+        let mut pred = truth.clone();
+        // Disconnected blob the ground truth doesn't have
+        for y in 14..18 {
+            for x in 26..30 {
+                pred[y * w + x] = 0.8;
+            }
+        }
+        let m = evaluate(&pred, &truth, w, h, None);
+        assert!(m.connectivity > 0.0);
+        assert!((m.sad - 16.0 * 0.8).abs() < 1e-3);
+
+        // The same blob attached to the disc is less disconnected
+        let mut attached = truth.clone();
+        for y in 14..18 {
+            for x in 18..22 {
+                attached[y * w + x] = attached[y * w + x].max(0.8);
+            }
+        }
+        let ma = evaluate(&attached, &truth, w, h, None);
+        assert!(ma.connectivity < m.connectivity);
+    }
+
+    #[test]
+    fn test_region_restricts_sums() {
+        let a = vec![1.0f32; 16];
+        let b = vec![0.0f32; 16];
+        let region: Vec<bool> = (0..16).map(|i| i < 4).collect();
+        let m = evaluate(&a, &b, 4, 4, Some(&region));
+        assert!((m.sad - 4.0).abs() < 1e-6);
+        assert!((m.mse - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_refinement_benchmark_on_soft_disc() {
+        // Coarse hard mask vs the refined one on a synthetic soft-edged subject
+        let (w, h) = (64, 64);
+        let truth = disc(w, h, 32.0, 32.0, 18.0);
+        let mut rgba = vec![0u8; w * h * 4];
+        for (i, &a) in truth.iter().enumerate() {
+            let v = (40.0 + 180.0 * a) as u8;
+            rgba[i * 4..i * 4 + 4].copy_from_slice(&[v, v, v, 255]);
+        }
+        let coarse: Vec<f32> = disc(w, h, 32.0, 32.0, 17.0)
+            .iter()
+            .map(|&a| if a >= 0.5 { 1.0 } else { 0.0 })
+            .collect();
+        let before = evaluate(&coarse, &truth, w, h, None);
+
+        let options = crate::options::PostProcessOptions::with_guide(4, 1e-4, 30, 0);
+        let refined = crate::refine_alpha(&coarse, &rgba, w, h, &options);
+        let after = evaluate(&refined, &truth, w, h, None);
+        assert!(after.sad < before.sad, "{after:?} vs {before:?}");
+        assert!(after.gradient < before.gradient, "{after:?} vs {before:?}");
+        assert!(after.iou >= before.iou, "{after:?} vs {before:?}");
+    }
+}
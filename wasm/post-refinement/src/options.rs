@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+/// `post_process_with_options` settings, deserialized from the worker's
+/// `PostProcessingConfig` object (camelCase keys, the same names as in
+/// types.ts). Missing keys take the TypeScript defaults; keys only the
+/// worker reads, such as `enabled`, are ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PostProcessOptions {
+    pub guide_radius: u32,
+    pub guide_eps: f32,
+    /// Scharr edge threshold, 0–255.
+    pub edge_threshold: u32,
+    pub feather_radius: u32,
+
+    /// RMS residual, in alpha units, at which the Poisson solve stops.
+    pub poisson_tolerance: f32,
+    /// "multigrid" or "sor".
+    pub poisson_method: String,
+    /// V-cycles or SOR sweeps before giving up (0 = the method's default).
+    pub poisson_max_iterations: u32,
+}
+
+impl PostProcessOptions {
+    /// Defaults with the four guided-filter settings of the positional exports.
+    pub fn with_guide(
+        guide_radius: u32,
+        guide_eps: f32,
+        edge_threshold: u32,
+        feather_radius: u32,
+    ) -> Self {
+        PostProcessOptions {
+            guide_radius,
+            guide_eps,
+            edge_threshold,
+            feather_radius,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for PostProcessOptions {
+    fn default() -> Self {
+        PostProcessOptions {
+            guide_radius: 8,
+            guide_eps: 0.01,
+            edge_threshold: 10,
+            feather_radius: 2,
+            poisson_tolerance: 1e-4,
+            poisson_method: "multigrid".into(),
+            poisson_max_iterations: 0,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::distance::euclidean_distance;
+use imaging::srgb::{build_srgb_to_linear_lut, linear_to_srgb};
+
+/// Total stroke width and softness are capped at this fraction of the
+/// longer image side.
+const MAX_OUTLINE_FRACTION: f32 = 0.5;
+
+/// Sticker outline: concentric strokes of `widths[k]` pixels in `colors[k]`
+/// around the cutout silhouette.
+///
+/// 1. Pad the canvas by the total stroke width so strokes are never clipped
+/// 2. Exact Euclidean distance from every pixel to the subject (α ≥ 0.5)
+/// 3. Stroke k covers distance ≤ R_k = w₀ + … + w_k, with a linear ramp of
+///    `softness` pixels centered on R_k for anti-aliasing
+/// 4. Composite bottom-up in linear light: outermost stroke first, the
+///    subject last, so inner strokes overlap outer ones
+///
+/// Distances are taken to the nearest subject pixel center minus half a
+/// pixel, which puts R = 0 on the silhouette edge itself.
+///
+/// Returns the RGBA result with its enlarged dimensions.
+pub fn sticker_outline(
+    rgba: &[u8],
+    w: usize,
+    h: usize,
+    widths: &[f32],
+    colors: &[[u8; 3]],
+    softness: f32,
+) -> (Vec<u8>, usize, usize) {
+    // Keep the padded canvas (and its allocations) within a few image sides
+    let max_total = w.max(h) as f32 * MAX_OUTLINE_FRACTION;
+    let softness = softness.clamp(0.0, max_total);
+    let total: f32 = widths
+        .iter()
+        .map(|s| s.max(0.0))
+        .sum::<f32>()
+        .min(max_total);
+    let pad = (total + softness * 0.5).ceil() as usize + 1;
+    let pw = w + 2 * pad;
+    let ph = h + 2 * pad;
+    let npx = pw * ph;
+
+    // Padded copy of the subject
+    let mut padded = vec![0u8; npx * 4];
+    for y in 0..h {
+        let src = y * w * 4;
+        let dst = ((y + pad) * pw + pad) * 4;
+        padded[dst..dst + w * 4].copy_from_slice(&rgba[src..src + w * 4]);
+    }
+
+    let seed: Vec<bool> = padded.chunks_exact(4).map(|p| p[3] >= 128).collect();
+    let dist = euclidean_distance(&seed, pw, ph);
+
+    // Cumulative outer radius of each stroke
+    let mut radii = Vec::with_capacity(widths.len());
+    let mut r = 0.0f32;
+    for &sw in widths {
+        r = (r + sw.max(0.0)).min(max_total);
+        radii.push(r);
+    }
+
+    let lut = build_srgb_to_linear_lut();
+    let stroke_lin: Vec<[f32; 3]> = colors
+        .iter()
+        .map(|c| [lut[c[0] as usize], lut[c[1] as usize], lut[c[2] as usize]])
+        .collect();
+
+    let mut out = vec![0u8; npx * 4];
+    for i in 0..npx {
+        let off = i * 4;
+        let d = if seed[i] { 0.0 } else { dist[i] - 0.5 };
+
+        // Premultiplied linear accumulator, outermost stroke first
+        let mut acc = [0.0f32; 4];
+        for k in (0..radii.len()).rev() {
+            let cov = coverage(d, radii[k], softness);
+            if cov <= 0.0 {
+                continue;
+            }
+            let inv = 1.0 - cov;
+            for c in 0..3 {
+                acc[c] = stroke_lin[k][c] * cov + acc[c] * inv;
+            }
+            acc[3] = cov + acc[3] * inv;
+        }
+
+        let a = padded[off + 3] as f32 / 255.0;
+        if a > 0.0 {
+            let inv = 1.0 - a;
+            for c in 0..3 {
+                acc[c] = lut[padded[off + c] as usize] * a + acc[c] * inv;
+            }
+            acc[3] = a + acc[3] * inv;
+        }
+
+        if acc[3] <= 0.0 {
+            continue;
+        }
+        let inv_a = 1.0 / acc[3];
+        for c in 0..3 {
+            out[off + c] = linear_to_srgb(acc[c] * inv_a);
+        }
+        out[off + 3] = (acc[3] * 255.0 + 0.5).min(255.0) as u8;
+    }
+
+    (out, pw, ph)
+}
+
+/// Stroke coverage at distance `d` for outer radius `r`.
+#[inline]
+fn coverage(d: f32, r: f32, softness: f32) -> f32 {
+    if softness <= 0.0 {
+        return if d <= r { 1.0 } else { 0.0 };
+    }
+    ((r + softness * 0.5 - d) / softness).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10×10 transparent canvas with an opaque white 4×4 square in the middle.
+    fn square() -> Vec<u8> {
+        let mut rgba = vec![0u8; 10 * 10 * 4];
+        for y in 3..7 {
+            for x in 3..7 {
+                rgba[(y * 10 + x) * 4..][..4].copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_stroke_radius_and_color() {
+        let (out, ow, oh) = sticker_outline(&square(), 10, 10, &[3.0], &[[255, 0, 0]], 0.0);
+        assert_eq!((ow, oh), (10 + 2 * 4, 10 + 2 * 4));
+        let px = |x: usize, y: usize| &out[((y + 4) * ow + x + 4) * 4..][..4];
+
+        assert_eq!(px(5, 5), &[255, 255, 255, 255], "subject kept");
+        assert_eq!(px(1, 5), &[255, 0, 0, 255], "2 px out: inside the stroke");
+        assert_eq!(px(7, 3), &[255, 0, 0, 255], "1 px out: inside the stroke");
+        assert_eq!(px(5, 10)[3], 0, "4 px out: past the stroke");
+        // The corner is rounded: 3 px out diagonally is ~3.7 px away
+        assert_eq!(px(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_strokes_stack_outward() {
+        let colors = [[255, 0, 0], [0, 0, 255]];
+        let (out, ow, _) = sticker_outline(&square(), 10, 10, &[1.0, 2.0], &colors, 0.0);
+        let px = |x: usize, y: usize| &out[((y + 4) * ow + x + 4) * 4..][..4];
+        assert_eq!(px(2, 5), &[255, 0, 0, 255]);
+        assert_eq!(px(0, 5), &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_width_capped_by_image_size() {
+        let (_, ow, oh) = sticker_outline(&square(), 10, 10, &[1e9], &[[0, 0, 0]], 1e9);
+        assert!(ow <= 10 + 2 * 11 && oh <= 10 + 2 * 11, "{ow}×{oh}");
+    }
+}
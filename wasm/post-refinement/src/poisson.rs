@@ -1,61 +1,435 @@
-/// Poisson gradient smoothing with SOR (Successive Over-Relaxation).
+/// Weight λ of the guidance Laplacian in the right-hand side.
+const GUIDE_WEIGHT: f32 = 0.3;
+/// Gauss-Seidel sweeps used to estimate ρ before switching to SOR.
+const OMEGA_PROBE_SWEEPS: usize = 6;
+/// Smoothing sweeps before and after each coarse-grid correction.
+const MG_SMOOTH_SWEEPS: usize = 2;
+/// Levels with fewer free pixels than this are solved by relaxation alone.
+const MG_COARSEST_FREE: usize = 64;
+const MG_COARSEST_SWEEPS: usize = 40;
+/// Iteration caps when the caller leaves them at 0: V-cycles, SOR sweeps.
+pub const DEFAULT_MAX_CYCLES: usize = 20;
+pub const DEFAULT_MAX_SWEEPS: usize = 2000;
+
+/// One level of the multigrid hierarchy.
+struct Grid {
+    w: usize,
+    h: usize,
+    free: Vec<bool>,
+}
+
+/// Smooth the transition zone of `alpha` in place by Poisson gradient
+/// smoothing, with convergence-controlled SOR or multigrid.
+///
+/// Solves the discrete Poisson equation on "free" pixels (transition zone
+/// 0.02 < α < 0.98), with definite FG/BG pixels locked as Dirichlet boundary
+/// conditions:
+///
+///   4·α(p) − Σ α(q) = 4·λ·Δg(p),   q ∈ N₄(p)
+///
+/// where Δg is the 5-point Laplacian of the luminance guide and λ = 0.3.
+///
+/// Iteration stops once the RMS Gauss-Seidel residual (in alpha units) drops
+/// below `tolerance`, so the result no longer depends on image resolution the
+/// way a fixed iteration count does.
 ///
-/// SOR converges ~2x faster than Gauss-Seidel for the same number of iterations
-/// by using an over-relaxation factor ω ∈ (1, 2).
+/// SOR ω selection (Hageman & Young, "Applied Iterative Methods", 1981, §9.5):
+/// a few Gauss-Seidel sweeps estimate its spectral radius ρ from the ratio of
+/// successive residuals, then ω = 2 / (1 + √(1 − ρ)). The grid formula
+/// ω ≈ 2 / (1 + sin(π/max(w,h))) assumes a full rectangle; the free region is
+/// usually a thin band, for which it over-relaxes badly.
 ///
-/// Optimal ω for Laplace equation on a grid: ω ≈ 2 / (1 + sin(π/max(w,h)))
-/// For practical purposes, ω = 1.5 works well for most image sizes.
+/// Multigrid: V-cycles with Gauss-Seidel smoothing, 2×2 cell restriction and
+/// piecewise-constant prolongation. Masks are irregular, so every coarse
+/// correction is scaled by the step length that minimizes the energy norm —
+/// the correction can never make the solution worse.
 ///
-/// Only operates on "free" pixels (transition zone 0.02 < α < 0.98).
-/// Definite FG/BG pixels are locked as boundary conditions.
-pub fn poisson_sor(alpha: &mut [f32], guide: &[f32], w: usize, h: usize, iterations: usize) {
-    if h < 3 || w < 3 || iterations == 0 {
+/// `max_iterations` counts SOR sweeps, or V-cycles when `multigrid` is set.
+pub fn poisson_smooth(
+    alpha: &mut [f32],
+    guide: &[f32],
+    w: usize,
+    h: usize,
+    tolerance: f32,
+    max_iterations: usize,
+    multigrid: bool,
+) {
+    if h < 3 || w < 3 || max_iterations == 0 {
         return;
     }
 
     let npx = w * h;
 
-    // Precompute target Laplacian from guidance gradient
-    let mut target_lap = vec![0.0f32; npx];
+    // Right-hand side from the guidance Laplacian, free mask from alpha
+    let mut rhs = vec![0.0f32; npx];
+    let mut free = vec![false; npx];
+    let mut free_count = 0usize;
     for y in 1..h - 1 {
         let yw = y * w;
         for x in 1..w - 1 {
             let idx = yw + x;
-            target_lap[idx] = guide[idx - 1] + guide[idx + 1] + guide[idx - w] + guide[idx + w]
-                - 4.0 * guide[idx];
+            if alpha[idx] > 0.02 && alpha[idx] < 0.98 {
+                free[idx] = true;
+                free_count += 1;
+                let lap = guide[idx - 1] + guide[idx + 1] + guide[idx - w] + guide[idx + w]
+                    - 4.0 * guide[idx];
+                rhs[idx] = 4.0 * GUIDE_WEIGHT * lap;
+            }
+        }
+    }
+    if free_count == 0 {
+        return;
+    }
+
+    solve(
+        alpha,
+        &rhs,
+        &free,
+        w,
+        h,
+        tolerance,
+        max_iterations,
+        multigrid,
+        true,
+    );
+}
+
+/// Solve (4I − N)·u = b on the `free` pixels of `u` in place.
+///
+/// Non-free pixels of `u` are Dirichlet boundary values; the outermost image
+/// ring is never free. `max_iterations` counts SOR sweeps, or V-cycles when
+/// `multigrid` is set. With `clamp`, the solution is projected onto [0, 1].
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve(
+    u: &mut [f32],
+    rhs: &[f32],
+    free: &[bool],
+    w: usize,
+    h: usize,
+    tolerance: f32,
+    max_iterations: usize,
+    multigrid: bool,
+    clamp: bool,
+) {
+    if h < 3 || w < 3 || max_iterations == 0 {
+        return;
+    }
+
+    if multigrid {
+        let levels = build_hierarchy(free.to_vec(), w, h);
+        for _cycle in 0..max_iterations {
+            if v_cycle(&levels, 0, u, rhs, clamp) < tolerance {
+                break;
+            }
         }
+        return;
     }
 
-    // Detect free (transition) pixels
-    let mut is_free = vec![false; npx];
-    for i in 0..npx {
-        is_free[i] = alpha[i] > 0.02 && alpha[i] < 0.98;
+    let mut omega = 1.0f32;
+    let mut prev_res = 0.0f32;
+    for iter in 0..max_iterations {
+        let res = relax(u, rhs, free, w, h, omega, clamp);
+        if res < tolerance {
+            break;
+        }
+        if iter + 1 == OMEGA_PROBE_SWEEPS && prev_res > 0.0 {
+            let rho = (res / prev_res).clamp(0.0, 0.999);
+            omega = (2.0 / (1.0 + (1.0 - rho).sqrt())).min(1.95);
+        }
+        prev_res = res;
     }
+}
 
-    // SOR parameters
-    let omega = 1.5f32; // Over-relaxation factor
-    let smooth_weight = 0.3f32; // Guidance gradient influence
+/// One SOR sweep over the free pixels of `u`, solving (4I − N)·u = b.
+///
+/// Returns the RMS Gauss-Seidel residual (b − A·u)/4 seen during the sweep.
+/// With `clamp`, updates are projected onto [0, 1] and pixels held at a bound
+/// by the projection don't count towards the residual.
+fn relax(
+    u: &mut [f32],
+    b: &[f32],
+    free: &[bool],
+    w: usize,
+    h: usize,
+    omega: f32,
+    clamp: bool,
+) -> f32 {
+    let mut sum_sq = 0.0f64;
+    let mut count = 0usize;
 
-    for _iter in 0..iterations {
-        for y in 1..h - 1 {
-            let yw = y * w;
-            for x in 1..w - 1 {
-                let idx = yw + x;
-                if !is_free[idx] {
+    for y in 0..h {
+        let yw = y * w;
+        for x in 0..w {
+            let idx = yw + x;
+            if !free[idx] {
+                continue;
+            }
+
+            let gs = 0.25 * (neighbor_sum(u, x, y, w, h) + b[idx]);
+            let delta = gs - u[idx];
+            let mut v = u[idx] + omega * delta;
+
+            if clamp {
+                let pinned = (u[idx] <= 0.0 && delta < 0.0) || (u[idx] >= 1.0 && delta > 0.0);
+                v = v.clamp(0.0, 1.0);
+                if pinned {
+                    u[idx] = v;
                     continue;
                 }
+            }
+
+            u[idx] = v;
+            sum_sq += (delta * delta) as f64;
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        0.0
+    } else {
+        (sum_sq / count as f64).sqrt() as f32
+    }
+}
+
+/// Sum of the four neighbors of (`x`, `y`). Cells outside the grid count as
+/// 0: the finest level never has free pixels on its outer ring, while a
+/// coarse cell on the edge can be free and its correction vanishes beyond it.
+#[inline]
+fn neighbor_sum(u: &[f32], x: usize, y: usize, w: usize, h: usize) -> f32 {
+    let idx = y * w + x;
+    let mut sum = 0.0;
+    if x > 0 {
+        sum += u[idx - 1];
+    }
+    if x + 1 < w {
+        sum += u[idx + 1];
+    }
+    if y > 0 {
+        sum += u[idx - w];
+    }
+    if y + 1 < h {
+        sum += u[idx + w];
+    }
+    sum
+}
+
+/// Residual r = b − A·u on free pixels, zero elsewhere.
+fn residual(u: &[f32], b: &[f32], free: &[bool], w: usize, h: usize, clamp: bool) -> Vec<f32> {
+    let mut r = vec![0.0f32; w * h];
+    for y in 0..h {
+        let yw = y * w;
+        for x in 0..w {
+            let idx = yw + x;
+            if !free[idx] {
+                continue;
+            }
+            let v = b[idx] - (4.0 * u[idx] - neighbor_sum(u, x, y, w, h));
+            // Pixels the projection holds at a bound must not drive the correction
+            let pinned = clamp && ((u[idx] <= 0.0 && v < 0.0) || (u[idx] >= 1.0 && v > 0.0));
+            if !pinned {
+                r[idx] = v;
+            }
+        }
+    }
+    r
+}
+
+/// Build the level hierarchy: a coarse cell is free if any of its 2×2 children is.
+fn build_hierarchy(free: Vec<bool>, w: usize, h: usize) -> Vec<Grid> {
+    let mut levels = vec![Grid { w, h, free }];
+
+    loop {
+        let fine = &levels[levels.len() - 1];
+        let free_count = fine.free.iter().filter(|&&f| f).count();
+        if fine.w < 8 || fine.h < 8 || free_count < MG_COARSEST_FREE {
+            break;
+        }
+
+        let cw = fine.w.div_ceil(2);
+        let ch = fine.h.div_ceil(2);
+        let mut free = vec![false; cw * ch];
+        for y in 0..fine.h {
+            for x in 0..fine.w {
+                if fine.free[y * fine.w + x] {
+                    free[(y / 2) * cw + x / 2] = true;
+                }
+            }
+        }
+        levels.push(Grid { w: cw, h: ch, free });
+    }
+
+    levels
+}
+
+/// One V-cycle on level `l`. Returns the residual of the final smoothing sweep.
+fn v_cycle(levels: &[Grid], l: usize, u: &mut [f32], b: &[f32], clamp: bool) -> f32 {
+    let g = &levels[l];
+    let (w, h) = (g.w, g.h);
+
+    if l + 1 == levels.len() {
+        let mut res = 0.0;
+        for _ in 0..MG_COARSEST_SWEEPS {
+            res = relax(u, b, &g.free, w, h, 1.0, clamp);
+        }
+        return res;
+    }
 
-                // 4-neighbor average
-                let avg =
-                    0.25 * (alpha[idx - 1] + alpha[idx + 1] + alpha[idx - w] + alpha[idx + w]);
+    for _ in 0..MG_SMOOTH_SWEEPS {
+        relax(u, b, &g.free, w, h, 1.0, clamp);
+    }
 
-                // Target: blend between smoothing and gradient-following
-                let gs_update = avg + smooth_weight * target_lap[idx];
+    // Restrict the residual. Piecewise-constant prolongation with cell-average
+    // restriction gives the Galerkin coarse operator ½·(4I − N), hence the ×2.
+    let r = residual(u, b, &g.free, w, h, clamp);
+    let c = &levels[l + 1];
+    let mut b_c = vec![0.0f32; c.w * c.h];
+    for y in 0..h {
+        for x in 0..w {
+            b_c[(y / 2) * c.w + x / 2] += r[y * w + x] * 0.5;
+        }
+    }
 
-                // SOR: α_new = α_old + ω * (gs_update - α_old)
-                let new_val = alpha[idx] + omega * (gs_update - alpha[idx]);
-                alpha[idx] = new_val.clamp(0.0, 1.0);
+    let mut e_c = vec![0.0f32; c.w * c.h];
+    v_cycle(levels, l + 1, &mut e_c, &b_c, false);
+
+    // Prolong onto free fine pixels
+    let mut e = vec![0.0f32; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let idx = y * w + x;
+            if g.free[idx] {
+                e[idx] = e_c[(y / 2) * c.w + x / 2];
             }
         }
     }
+
+    // Energy-minimizing step length: s = ⟨r, e⟩ / ⟨e, A·e⟩
+    let mut num = 0.0f64;
+    let mut den = 0.0f64;
+    for y in 0..h {
+        let yw = y * w;
+        for x in 0..w {
+            let idx = yw + x;
+            if !g.free[idx] {
+                continue;
+            }
+            let ae = 4.0 * e[idx] - neighbor_sum(&e, x, y, w, h);
+            num += (r[idx] * e[idx]) as f64;
+            den += (e[idx] * ae) as f64;
+        }
+    }
+    if den > 1e-12 {
+        let s = (num / den) as f32;
+        for (i, &ei) in e.iter().enumerate() {
+            if g.free[i] {
+                u[i] += s * ei;
+                if clamp {
+                    u[i] = u[i].clamp(0.0, 1.0);
+                }
+            }
+        }
+    }
+
+    let mut res = 0.0;
+    for _ in 0..MG_SMOOTH_SWEEPS {
+        res = relax(u, b, &g.free, w, h, 1.0, clamp);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opaque disc of radius `r1` inside a transparent canvas, with a free
+    /// annulus out to `r2` at α = 0.5. With a flat guide the exact solution
+    /// in the annulus is ln(r / r2) / ln(r1 / r2).
+    fn annulus(size: usize, r1: f32, r2: f32) -> (Vec<f32>, Vec<f32>) {
+        let c = size as f32 / 2.0;
+        let alpha = (0..size * size)
+            .map(|i| {
+                let dx = (i % size) as f32 + 0.5 - c;
+                let dy = (i / size) as f32 + 0.5 - c;
+                let r = dx.hypot(dy);
+                if r <= r1 {
+                    1.0
+                } else if r >= r2 {
+                    0.0
+                } else {
+                    0.5
+                }
+            })
+            .collect();
+        (alpha, vec![0.5; size * size])
+    }
+
+    fn exact(size: usize, r1: f32, r2: f32, x: usize, y: usize) -> Option<f32> {
+        let c = size as f32 / 2.0;
+        let r = (x as f32 + 0.5 - c).hypot(y as f32 + 0.5 - c);
+        (r > r1 + 1.0 && r < r2 - 1.0).then(|| (r / r2).ln() / (r1 / r2).ln())
+    }
+
+    /// RMS of (b − A·u)/4 over the free pixels (b = 0 for a flat guide).
+    fn rms_residual(u: &[f32], free: &[bool], w: usize, h: usize) -> f32 {
+        let r = residual(u, &vec![0.0; w * h], free, w, h, false);
+        let n = free.iter().filter(|&&f| f).count() as f32;
+        (r.iter().map(|v| (v / 4.0) * (v / 4.0)).sum::<f32>() / n).sqrt()
+    }
+
+    #[test]
+    fn test_residual_below_tolerance() {
+        for multigrid in [false, true] {
+            let (mut alpha, guide) = annulus(64, 8.0, 24.0);
+            let free: Vec<bool> = alpha.iter().map(|&a| a > 0.02 && a < 0.98).collect();
+            let tol = 1e-5;
+            poisson_smooth(&mut alpha, &guide, 64, 64, tol, 10_000, multigrid);
+            let res = rms_residual(&alpha, &free, 64, 64);
+            assert!(res < 2.0 * tol, "multigrid {multigrid}: residual {res}");
+        }
+    }
+
+    #[test]
+    fn test_sor_and_multigrid_agree() {
+        let (mut sor, guide) = annulus(64, 8.0, 24.0);
+        let mut mg = sor.clone();
+        poisson_smooth(&mut sor, &guide, 64, 64, 1e-6, 10_000, false);
+        poisson_smooth(&mut mg, &guide, 64, 64, 1e-6, 200, true);
+        let diff = sor
+            .iter()
+            .zip(&mg)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(diff < 1e-3, "max difference {diff}");
+    }
+
+    #[test]
+    fn test_result_independent_of_resolution() {
+        for scale in [1, 2] {
+            let size = 64 * scale;
+            let (r1, r2) = (8.0 * scale as f32, 24.0 * scale as f32);
+            let (mut alpha, guide) = annulus(size, r1, r2);
+            poisson_smooth(&mut alpha, &guide, size, size, 1e-4, 50, true);
+            for y in 0..size {
+                for x in 0..size {
+                    if let Some(want) = exact(size, r1, r2, x, y) {
+                        let got = alpha[y * size + x];
+                        assert!(
+                            (got - want).abs() < 0.03,
+                            "{scale}×, ({x}, {y}): {got} vs {want}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_coarse_grid_updates_edge_cells() {
+        // Free cells on row 0 and column 0 of a coarse grid must relax too
+        let (w, h) = (4, 4);
+        let free = vec![true; w * h];
+        let mut u = vec![1.0; w * h];
+        relax(&mut u, &vec![0.0; w * h], &free, w, h, 1.0, false);
+        assert!(u[0] < 1.0 && u[w] < 1.0 && u[1] < 1.0);
+    }
 }
@@ -0,0 +1,257 @@
+use crate::poisson;
+
+const TOLERANCE: f32 = 1e-4;
+const MAX_CYCLES: usize = 30;
+/// Alpha from which a cutout pixel belongs to the cloned region Ω.
+const SOLID_ALPHA: f32 = 0.5;
+
+/// Paste `src` (sw × sh, alpha = cutout) into `dst` (dw × dh) at (ox, oy) by
+/// seamless cloning — Poisson image editing (Pérez, Gangnet & Blake, 2003).
+///
+/// The cutout is pasted at (ox, oy) by solving, per channel, for the pasted
+/// region Ω whose gradients match a guidance field v while its boundary takes
+/// the target's values:
+///
+///   4·f(p) − Σ f(q) = Σ v(p, q),   q ∈ N₄(p),   f = target outside Ω
+///
+/// - Source gradients: v(p, q) = s(p) − s(q) — the cutout keeps its detail
+///   but takes on the target's colors and lighting at the seam
+/// - Mixed gradients: whichever of the source and target gradient is stronger,
+///   so target texture shows through flat parts of the cutout
+///
+/// Ω is the solid part of the cutout (α ≥ `SOLID_ALPHA`): the colors of
+/// nearly transparent fringe pixels are unreliable, and their gradients would
+/// bring back the halo the cutout removed. Edges into the fringe get no
+/// guidance (the source counts as flat there). The solution is blended over
+/// the target by the cutout's alpha so soft edges stay soft. Fringe pixels are
+/// blended by alpha too, as their source color shifted by the correction f − s
+/// of the adjacent Ω pixels (Ω's mean correction when none is adjacent), so
+/// the edge fades out instead of being cut at `SOLID_ALPHA`. The system is
+/// solved with the multigrid solver of poisson.rs, in sRGB [0, 1], on Ω's
+/// bounding box plus its one-pixel Dirichlet ring instead of the whole target.
+#[allow(clippy::too_many_arguments)]
+pub fn seamless_clone(
+    src: &[u8],
+    sw: usize,
+    sh: usize,
+    dst: &[u8],
+    dw: usize,
+    dh: usize,
+    ox: isize,
+    oy: isize,
+    mixed: bool,
+) -> Vec<u8> {
+    let inv255 = 1.0 / 255.0;
+
+    // Target pixel → source pixel
+    let src_index = |x: usize, y: usize| -> Option<usize> {
+        let sx = x as isize - ox;
+        let sy = y as isize - oy;
+        if sx < 0 || sy < 0 || sx >= sw as isize || sy >= sh as isize {
+            None
+        } else {
+            Some(sy as usize * sw + sx as usize)
+        }
+    };
+    let alpha_at =
+        |x: usize, y: usize| src_index(x, y).map_or(0.0, |si| src[si * 4 + 3] as f32 * inv255);
+    // The image ring is never in Ω: it stays Dirichlet
+    let in_omega = |x: usize, y: usize| {
+        x > 0 && y > 0 && x + 1 < dw && y + 1 < dh && alpha_at(x, y) >= SOLID_ALPHA
+    };
+
+    // Bounding box of Ω, grown by its Dirichlet ring
+    let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+    for y in 0..dh {
+        for x in 0..dw {
+            if in_omega(x, y) {
+                x0 = x0.min(x);
+                y0 = y0.min(y);
+                x1 = x1.max(x);
+                y1 = y1.max(y);
+            }
+        }
+    }
+    let mut out = dst.to_vec();
+    if x0 == usize::MAX {
+        return out;
+    }
+    let (x0, y0, x1, y1) = (x0 - 1, y0 - 1, x1 + 1, y1 + 1);
+    let (bw, bh) = (x1 - x0 + 1, y1 - y0 + 1);
+
+    // Box-local grid: pixel (bx, by) is target pixel (x0 + bx, y0 + by)
+    let mut cover = vec![0.0f32; bw * bh];
+    let mut free = vec![false; bw * bh];
+    for by in 0..bh {
+        for bx in 0..bw {
+            let (x, y) = (x0 + bx, y0 + by);
+            cover[by * bw + bx] = alpha_at(x, y);
+            free[by * bw + bx] = in_omega(x, y);
+        }
+    }
+
+    let mut f = vec![0.0f32; bw * bh];
+    let mut rhs = vec![0.0f32; bw * bh];
+    for c in 0..3 {
+        let t = |x: usize, y: usize| dst[(y * dw + x) * 4 + c] as f32 * inv255;
+        let s = |si: usize| src[si * 4 + c] as f32 * inv255;
+
+        for by in 0..bh {
+            for bx in 0..bw {
+                let (x, y) = (x0 + bx, y0 + by);
+                let i = by * bw + bx;
+                rhs[i] = 0.0;
+                f[i] = t(x, y);
+                if !free[i] {
+                    continue;
+                }
+
+                // Initial guess: the source itself
+                let si = src_index(x, y).unwrap_or(0);
+                f[i] = s(si);
+
+                let sp = s(si);
+                let tp = t(x, y);
+                let mut div = 0.0f32;
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    // Fringe and area outside the cutout: the source is flat
+                    let gs = if alpha_at(nx, ny) >= SOLID_ALPHA {
+                        src_index(nx, ny).map_or(0.0, |sn| sp - s(sn))
+                    } else {
+                        0.0
+                    };
+                    let g = if mixed {
+                        let gt = tp - t(nx, ny);
+                        if gt.abs() > gs.abs() {
+                            gt
+                        } else {
+                            gs
+                        }
+                    } else {
+                        gs
+                    };
+                    div += g;
+                }
+                rhs[i] = div;
+            }
+        }
+
+        poisson::solve(
+            &mut f, &rhs, &free, bw, bh, TOLERANCE, MAX_CYCLES, true, true,
+        );
+
+        // Correction f − s the solve applied to the source, averaged over Ω
+        let mut mean_shift = 0.0f32;
+        for by in 0..bh {
+            for bx in 0..bw {
+                let i = by * bw + bx;
+                if free[i] {
+                    let (x, y) = (x0 + bx, y0 + by);
+                    let a = cover[i];
+                    let v = f[i] * a + t(x, y) * (1.0 - a);
+                    out[(y * dw + x) * 4 + c] = (v * 255.0 + 0.5).clamp(0.0, 255.0) as u8;
+                    mean_shift += f[i] - s(src_index(x, y).unwrap_or(0));
+                }
+            }
+        }
+        mean_shift /= free.iter().filter(|&&fr| fr).count() as f32;
+
+        // Fringe: the source shifted by the correction of the adjacent Ω
+        // pixels (or Ω's mean), blended over the target by alpha
+        for y in oy.max(0) as usize..(oy + sh as isize).clamp(0, dh as isize) as usize {
+            for x in ox.max(0) as usize..(ox + sw as isize).clamp(0, dw as isize) as usize {
+                let a = alpha_at(x, y);
+                if a <= 0.0 || in_omega(x, y) {
+                    continue;
+                }
+                let (mut shift, mut n) = (0.0f32, 0);
+                for ny in y.saturating_sub(1)..=(y + 1).min(dh - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(dw - 1) {
+                        if in_omega(nx, ny) {
+                            let i = (ny - y0) * bw + (nx - x0);
+                            shift += f[i] - s(src_index(nx, ny).unwrap_or(0));
+                            n += 1;
+                        }
+                    }
+                }
+                let shift = if n > 0 { shift / n as f32 } else { mean_shift };
+                let si = src_index(x, y).unwrap_or(0);
+                let v = ((s(si) + shift) * a + t(x, y) * (1.0 - a)).clamp(0.0, 1.0);
+                out[(y * dw + x) * 4 + c] = (v * 255.0 + 0.5) as u8;
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `w × h` RGBA from a per-pixel gray value and alpha.
+    fn image(w: usize, h: usize, px: impl Fn(usize, usize) -> (u8, u8)) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(w * h * 4);
+        for y in 0..h {
+            for x in 0..w {
+                let (v, a) = px(x, y);
+                rgba.extend_from_slice(&[v, v, v, a]);
+            }
+        }
+        rgba
+    }
+
+    #[test]
+    fn test_constant_source_on_constant_target() {
+        // Opaque 8×8 core with a soft fringe ring
+        let src = image(12, 12, |x, y| {
+            let ring = x.min(y).min(11 - x).min(11 - y);
+            (220, [0, 60, 200, 255][ring.min(3)])
+        });
+        let dst = image(24, 24, |_, _| (40, 255));
+        let out = seamless_clone(&src, 12, 12, &dst, 24, 24, 6, 6, false);
+        assert!(out.iter().zip(&dst).all(|(a, b)| a.abs_diff(*b) <= 1));
+    }
+
+    #[test]
+    fn test_boundary_matches_target() {
+        // Flat source with a bright spot, pasted opaque onto a flat target
+        let spot = |x: usize, y: usize| (4..6).contains(&x) && (4..6).contains(&y);
+        let src = image(10, 10, |x, y| (if spot(x, y) { 220 } else { 80 }, 255));
+        let dst = image(20, 20, |_, _| (128, 255));
+        let out = seamless_clone(&src, 10, 10, &dst, 20, 20, 5, 5, false);
+        let at = |x: usize, y: usize| out[(y * 20 + x) * 4] as i32;
+        for k in 4..16 {
+            // Ring around the pasted square is untouched
+            for (x, y) in [(k, 4), (k, 15), (4, k), (15, k)] {
+                assert_eq!(at(x, y), 128);
+            }
+        }
+        // Ω takes on the target's level at its edge and keeps the spot
+        for k in 5..15 {
+            for (x, y) in [(k, 5), (k, 14), (5, k), (14, k)] {
+                assert!((at(x, y) - 128).abs() <= 3, "({x}, {y}): {}", at(x, y));
+            }
+        }
+        assert!(at(9, 9) - at(6, 6) > 100);
+    }
+
+    #[test]
+    fn test_fringe_blended_by_alpha() {
+        // Solid gray 100 core, fringe of brighter 200 at alpha 0.4
+        let src = image(10, 10, |x, y| {
+            if (2..8).contains(&x) && (2..8).contains(&y) {
+                (100, 255)
+            } else {
+                (200, 102)
+            }
+        });
+        let dst = image(20, 20, |_, _| (50, 255));
+        let out = seamless_clone(&src, 10, 10, &dst, 20, 20, 5, 5, false);
+        // Core shifted by −50 onto the target; the fringe keeps its detail
+        // over that shift at 40%: 0.4·(200 − 50) + 0.6·50 = 90
+        let fringe = out[(5 * 20 + 5) * 4] as i32;
+        assert!((fringe - 90).abs() <= 2, "{fringe}");
+        assert_eq!(out[(4 * 20 + 4) * 4], 50);
+    }
+}
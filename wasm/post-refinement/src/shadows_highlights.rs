@@ -0,0 +1,140 @@
+use crate::fast_guided_filter::fast_guided_filter;
+
+/// Guided filter ε: luma steps with σ above ~0.1 count as edges.
+const BASE_EPS: f32 = 0.01;
+const STRENGTH: f32 = 3.0;
+/// Luma offset (0–1) that keeps the ratio finite in near-black pixels.
+const RATIO_EPS: f32 = 1.0 / 255.0;
+
+/// Lift the shadows and recover the highlights of `rgba` in place, by local
+/// tone mapping on an edge-aware base layer.
+///
+/// Luma is split into base B (self-guided filter of luma: smooth inside
+/// regions, sharp at their edges) and detail D = L − B. Only the base is
+/// tone mapped and the detail is added back, so texture keeps its local
+/// contrast and, since B follows edges, no halo forms along them.
+///
+/// Tone curve on the base (u = B / width, `amount` ∈ [0, 1]):
+///
+///   shadows:    B += 3·amount · B · (1 − u)²            for B < width
+///   highlights: B −= 3·amount · (1 − B) · (1 − u')²     for 1 − B < width
+///
+/// with u' = (1 − B) / width. Both terms vanish at 0 and 1 (black and white
+/// stay put) and at the width boundary (no kink), and the factor 3 is the
+/// largest for which each curve stays monotone. The highlight curve is
+/// applied to the shadow curve's output, so even with overlapping widths the
+/// combined mapping is monotone — tones never swap order.
+/// `width` (0–1) is the tonal range affected, like Photoshop's "Tonal Width".
+///
+/// The pixel's RGB is scaled by the luma ratio (hue and saturation kept),
+/// capped so no channel clips.
+#[allow(clippy::too_many_arguments)]
+pub fn shadows_highlights(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    shadows: f32,
+    highlights: f32,
+    shadow_width: f32,
+    highlight_width: f32,
+    radius: usize,
+) {
+    let shadows = shadows.clamp(0.0, 1.0);
+    let highlights = highlights.clamp(0.0, 1.0);
+    if (shadows == 0.0 && highlights == 0.0) || w == 0 || h == 0 {
+        return;
+    }
+    let shadow_width = shadow_width.clamp(0.01, 1.0);
+    let highlight_width = highlight_width.clamp(0.01, 1.0);
+    let radius = if radius == 0 {
+        (w.max(h) / 40).max(2)
+    } else {
+        radius
+    };
+
+    let inv255 = 1.0 / 255.0;
+    let luma: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| (p[0] as f32 * 0.2126 + p[1] as f32 * 0.7152 + p[2] as f32 * 0.0722) * inv255)
+        .collect();
+    let subsample = 4usize.min(w.min(h) / 8).max(1);
+    let base = fast_guided_filter(&luma, &luma, w, h, radius, BASE_EPS, subsample);
+
+    for ((px, &l), &b) in rgba.chunks_exact_mut(4).zip(&luma).zip(&base) {
+        let mut mapped = b;
+        if mapped < shadow_width {
+            let v = 1.0 - mapped / shadow_width;
+            mapped += STRENGTH * shadows * mapped * v * v;
+        }
+        let d = 1.0 - mapped;
+        if d < highlight_width {
+            let v = 1.0 - d / highlight_width;
+            mapped -= STRENGTH * highlights * d * v * v;
+        }
+        if mapped == b {
+            continue;
+        }
+
+        let target = (l + mapped - b).clamp(0.0, 1.0);
+        let peak = px[0].max(px[1]).max(px[2]).max(1) as f32;
+        let scale = ((target + RATIO_EPS) / (l + RATIO_EPS)).min(255.0 / peak);
+        for c in px.iter_mut().take(3) {
+            *c = (*c as f32 * scale + 0.5).min(255.0) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 48×32 gray image, `left` levels left of x = 24 and `right` from there.
+    fn split(left: u8, right: u8) -> Vec<u8> {
+        (0..48 * 32)
+            .flat_map(|i| {
+                let v = if i % 48 < 24 { left } else { right };
+                [v, v, v, 255]
+            })
+            .collect()
+    }
+
+    fn at(rgba: &[u8], x: usize) -> u8 {
+        rgba[(16 * 48 + x) * 4]
+    }
+
+    #[test]
+    fn test_zero_amount_is_identity() {
+        let rgba = split(30, 230);
+        let mut buf = rgba.clone();
+        shadows_highlights(&mut buf, 48, 32, 0.0, 0.0, 0.5, 0.5, 0);
+        assert_eq!(buf, rgba);
+    }
+
+    #[test]
+    fn test_shadows_lift_and_highlights_stay() {
+        let mut buf = split(30, 230);
+        shadows_highlights(&mut buf, 48, 32, 0.8, 0.0, 0.5, 0.5, 0);
+        assert!(at(&buf, 4) > 50, "{}", at(&buf, 4));
+        assert_eq!(at(&buf, 44), 230);
+
+        let mut buf = split(30, 230);
+        shadows_highlights(&mut buf, 48, 32, 0.0, 0.8, 0.5, 0.5, 0);
+        assert_eq!(at(&buf, 4), 30);
+        assert!(at(&buf, 44) < 215, "{}", at(&buf, 44));
+    }
+
+    #[test]
+    fn test_edges_get_no_halo() {
+        // The base layer stops at the edge: dark pixels next to it are lifted
+        // like distant ones, and the bright side is not pulled along
+        let mut buf = split(30, 200);
+        shadows_highlights(&mut buf, 48, 32, 0.8, 0.0, 0.5, 0.5, 4);
+        assert!(
+            at(&buf, 23).abs_diff(at(&buf, 4)) <= 3,
+            "{} vs {}",
+            at(&buf, 23),
+            at(&buf, 4)
+        );
+        assert!(at(&buf, 24).abs_diff(200) <= 3, "{}", at(&buf, 24));
+    }
+}
@@ -20,10 +20,9 @@ struct ColorSample {
     dist_sq: i32,
 }
 
-pub fn shared_matting(alpha: &mut [f32], rgba: &[u8], trimap: &[u8], w: usize, h: usize) {
-    let max_search = 25isize;
-
-    // Precompute spiral search order (sorted by squared distance — no sqrt needed)
+/// Spiral search order: offsets within `max_search` (excluding the origin),
+/// sorted by squared distance — no sqrt needed.
+pub fn spiral_offsets(max_search: isize) -> Vec<(isize, isize, i32)> {
     let mut spiral: Vec<(isize, isize, i32)> =
         Vec::with_capacity(((2 * max_search + 1) * (2 * max_search + 1)) as usize);
     for dy in -max_search..=max_search {
@@ -36,6 +35,13 @@ pub fn shared_matting(alpha: &mut [f32], rgba: &[u8], trimap: &[u8], w: usize, h
         }
     }
     spiral.sort_unstable_by_key(|s| s.2);
+    spiral
+}
+
+pub fn shared_matting(alpha: &mut [f32], rgba: &[u8], trimap: &[u8], w: usize, h: usize) {
+    let max_search = 25isize;
+
+    let spiral = spiral_offsets(max_search);
 
     // Pre-allocate sample buffers (reused per pixel)
     let mut fg_samples: Vec<ColorSample> = Vec::with_capacity(MAX_SAMPLES);
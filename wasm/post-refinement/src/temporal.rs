@@ -0,0 +1,121 @@
+use crate::blur::box_blur_separable;
+
+const MOTION_RADIUS: usize = 2;
+
+/// Smooth `alphas` (one 0–1 matte per frame) in place across the sequence,
+/// gated by the motion in `lumas` (0–1).
+///
+/// Each frame is refined independently, so tiny changes in the input mask
+/// make edges flicker from frame to frame. Where the image does not move,
+/// the alpha should not either:
+///
+/// 1. Motion map per frame pair: |Y(t) − Y(t−1)| box-averaged over
+///    `MOTION_RADIUS`, so sensor noise doesn't count as motion
+/// 2. Blend weight k = strength · exp(−(m / motion_threshold)²): close to
+///    `strength` on static pixels, ~0 where something moved
+/// 3. Exponential smoothing F(t) = k·F(t−1) + (1 − k)·A(t), run forward and
+///    backward over the whole sequence and averaged, so static regions are
+///    smoothed without the lag a one-sided filter leaves behind
+///
+/// Moving edges take the current frame's alpha, so they follow motion
+/// instead of ghosting. `motion_threshold` is in the same units as `lumas`.
+pub fn stabilize(
+    alphas: &mut [Vec<f32>],
+    lumas: &[Vec<f32>],
+    w: usize,
+    h: usize,
+    strength: f32,
+    motion_threshold: f32,
+) {
+    let frames = alphas.len();
+    if frames < 2 || strength <= 0.0 {
+        return;
+    }
+    let strength = strength.min(0.95);
+    let inv_thr = 1.0 / motion_threshold.max(1e-4);
+
+    // keep[t]: blend weight between frame t − 1 and t
+    let mut keep: Vec<Vec<f32>> = vec![Vec::new(); frames];
+    for t in 1..frames {
+        let diff: Vec<f32> = lumas[t]
+            .iter()
+            .zip(&lumas[t - 1])
+            .map(|(a, b)| (a - b).abs())
+            .collect();
+        let motion = box_blur_separable(&diff, w, h, MOTION_RADIUS);
+        keep[t] = motion
+            .iter()
+            .map(|&m| {
+                let x = m * inv_thr;
+                strength * (-x * x).exp()
+            })
+            .collect();
+    }
+
+    // Forward pass
+    let mut forward = alphas.to_vec();
+    for t in 1..frames {
+        let (done, rest) = forward.split_at_mut(t);
+        let prev = &done[t - 1];
+        for ((f, &p), &k) in rest[0].iter_mut().zip(prev).zip(&keep[t]) {
+            *f = k * p + (1.0 - k) * *f;
+        }
+    }
+
+    // Backward pass, averaged with the forward one
+    let mut backward = alphas[frames - 1].clone();
+    for (v, &fw) in alphas[frames - 1].iter_mut().zip(&forward[frames - 1]) {
+        *v = (*v + fw) * 0.5;
+    }
+    for t in (0..frames - 1).rev() {
+        for (i, b) in backward.iter_mut().enumerate() {
+            let k = keep[t + 1][i];
+            *b = k * *b + (1.0 - k) * alphas[t][i];
+        }
+        for (v, (&b, &fw)) in alphas[t].iter_mut().zip(backward.iter().zip(&forward[t])) {
+            *v = (b + fw) * 0.5;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: usize = 8;
+    const H: usize = 8;
+
+    #[test]
+    fn test_constant_sequence_is_fixed_point() {
+        let alpha: Vec<f32> = (0..W * H).map(|i| (i % W) as f32 / W as f32).collect();
+        let mut alphas = vec![alpha.clone(); 5];
+        let lumas = vec![vec![0.5; W * H]; 5];
+        stabilize(&mut alphas, &lumas, W, H, 0.9, 0.05);
+        for frame in &alphas {
+            for (a, b) in frame.iter().zip(&alpha) {
+                assert!((a - b).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_static_flicker_is_damped() {
+        let mut alphas: Vec<Vec<f32>> = (0..6)
+            .map(|t| vec![if t % 2 == 0 { 0.4 } else { 0.6 }; W * H])
+            .collect();
+        let lumas = vec![vec![0.5; W * H]; 6];
+        stabilize(&mut alphas, &lumas, W, H, 0.9, 0.05);
+        for frame in &alphas[1..5] {
+            assert!((frame[0] - 0.5).abs() < 0.05, "{}", frame[0]);
+        }
+    }
+
+    #[test]
+    fn test_motion_keeps_current_alpha() {
+        let mut alphas = vec![vec![0.0; W * H], vec![1.0; W * H]];
+        let lumas = vec![vec![0.0; W * H], vec![1.0; W * H]];
+        stabilize(&mut alphas, &lumas, W, H, 0.9, 0.05);
+        assert!(alphas[0].iter().all(|&a| a < 1e-3));
+        assert!(alphas[1].iter().all(|&a| a > 1.0 - 1e-3));
+    }
+}
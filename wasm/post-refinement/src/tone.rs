@@ -0,0 +1,60 @@
+use wasm_bindgen::prelude::*;
+
+/// Haze removal (dark channel prior) for washed-out outdoor photos.
+///
+/// `strength` (0–1) sets how much haze is removed; 1 keeps the slight trace
+/// of haze that preserves the sense of depth. The transmission map is refined
+/// with the fast guided filter, so edges stay halo-free. Returns RGBA with
+/// alpha unchanged; invalid input returns the input copy.
+#[wasm_bindgen]
+pub fn dehaze(rgba: &[u8], width: u32, height: u32, strength: f32) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+
+    let mut out = rgba.to_vec();
+    crate::dehaze::dehaze(&mut out, w, h, strength);
+    out
+}
+
+/// Shadows / highlights: lift dark areas and recover bright ones locally.
+///
+/// `shadows` and `highlights` (0–1) set the amount; `shadow_width` and
+/// `highlight_width` (0–1) set how far into the midtones each reaches
+/// (0.5 ≈ the darkest / brightest half). Tones are mapped on an edge-aware
+/// base layer (guided filter of luma, `radius` pixels, 0 = auto) and the
+/// detail is added back, so texture is kept and edges get no halos. Returns
+/// RGBA with alpha unchanged; invalid input returns the input copy.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn shadows_highlights(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    shadows: f32,
+    highlights: f32,
+    shadow_width: f32,
+    highlight_width: f32,
+    radius: u32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+
+    let mut out = rgba.to_vec();
+    crate::shadows_highlights::shadows_highlights(
+        &mut out,
+        w,
+        h,
+        shadows,
+        highlights,
+        shadow_width,
+        highlight_width,
+        radius as usize,
+    );
+    out
+}
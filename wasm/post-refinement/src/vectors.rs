@@ -0,0 +1,61 @@
+use crate::contour;
+use wasm_bindgen::prelude::*;
+
+/// Silhouette outline as SVG path data, for die-cut stickers and cutters.
+///
+/// Traces alpha above `alpha_threshold` with marching squares, grown by
+/// `offset` pixels (negative shrinks), simplifies with Ramer–Douglas–Peucker at
+/// `tolerance` pixels and, with `smooth`, fits cubic Béziers. Holes wind
+/// opposite to outer contours, so the path renders with the nonzero fill rule.
+/// Returns an empty string on invalid input or an empty mask.
+#[wasm_bindgen]
+pub fn mask_to_svg_path(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    alpha_threshold: u8,
+    offset: f32,
+    tolerance: f32,
+    smooth: bool,
+) -> String {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return String::new();
+    }
+
+    let contours = contour::trace_contours(rgba, w, h, alpha_threshold, offset, tolerance);
+    contour::svg_path(&contours, smooth)
+}
+
+/// Silhouette outline as plain polygons (same tracing as `mask_to_svg_path`).
+///
+/// Returns `[count, then per contour: n, is_hole (0/1), x0, y0, …, x(n−1), y(n−1)]`
+/// in pixel coordinates, or an empty Vec on invalid input.
+#[wasm_bindgen]
+pub fn mask_to_contours(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    alpha_threshold: u8,
+    offset: f32,
+    tolerance: f32,
+) -> Vec<f32> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return Vec::new();
+    }
+
+    let contours = contour::trace_contours(rgba, w, h, alpha_threshold, offset, tolerance);
+    let mut out = vec![contours.len() as f32];
+    for c in &contours {
+        let pts = c.simplified();
+        out.push(pts.len() as f32);
+        out.push(if c.hole { 1.0 } else { 0.0 });
+        for p in pts {
+            out.extend_from_slice(&p);
+        }
+    }
+    out
+}
@@ -0,0 +1,276 @@
+const MAD_TO_SIGMA: f32 = 1.0 / 0.6745;
+const NOISE_FLOOR: f32 = 1.5;
+const TARGET_CONTRAST: f32 = 0.2;
+const MAX_CLAHE_CLIP: f32 = 3.0;
+const CLAHE_TILE_PX: usize = 128;
+const TARGET_SHARPNESS: f32 = 0.6;
+const MAX_SHARPEN: f32 = 0.6;
+/// NLM strength h per unit of noise σ.
+const NLM_H_PER_SIGMA: f32 = 1.2;
+
+/// Gradient energy of white noise σ² per pixel: forward differences in x and y.
+const NOISE_GRAD_ENERGY: f32 = 4.0;
+/// The same after a 3×3 box blur: 2 · 6 / 81.
+const NOISE_GRAD_ENERGY_BLURRED: f32 = 12.0 / 81.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ImageStats {
+    pub noise_sigma: f32,
+    pub contrast: f32,
+    pub sharpness: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Recommendation {
+    pub denoise_radius: u32,
+    pub nlm_h: f32,
+    pub nlm_search_radius: u32,
+    pub clahe_clip: f32,
+    pub clahe_grid: u32,
+    pub sharpen_strength: f32,
+}
+
+/// Noise, contrast and sharpness of the image, in O(w*h); `recommend` turns
+/// them into `pre_process` parameters.
+///
+/// Measurements (on BT.709 luma, 0–255 units):
+/// - Noise σ: median absolute value of the finest Haar HH band / 0.6745
+///   (Donoho & Johnstone). HH of 2×2 blocks is (a − b − c + d)/2, which keeps
+///   white noise at σ while smooth content and straight edges cancel out
+/// - Contrast: RMS contrast, std(Y) / 255
+/// - Sharpness: 1 − E|∇B|² / E|∇Y|², B = 3×3 box blur of Y. Blurring barely
+///   changes an already soft edge (→ 0) but spreads a crisp one (step → 2/3).
+///   The expected noise energy is subtracted from both terms first
+pub fn analyze(rgba: &[u8], w: usize, h: usize) -> ImageStats {
+    let lum: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| 0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32)
+        .collect();
+    let noise_sigma = estimate_noise(&lum, w, h);
+
+    let n = lum.len().max(1) as f64;
+    let mean = lum.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let var = lum.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n;
+    let contrast = (var.sqrt() / 255.0) as f32;
+
+    let blurred = box3(&lum, w, h);
+    let sigma2 = noise_sigma * noise_sigma;
+    let e_orig = (gradient_energy(&lum, w, h) - NOISE_GRAD_ENERGY * sigma2).max(0.0);
+    let e_blur = (gradient_energy(&blurred, w, h) - NOISE_GRAD_ENERGY_BLURRED * sigma2).max(0.0);
+    let sharpness = if e_orig > 1e-3 {
+        (1.0 - e_blur / e_orig).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    ImageStats {
+        noise_sigma,
+        contrast,
+        sharpness,
+    }
+}
+
+/// `pre_process` parameters for an image with `stats`.
+///
+/// Recommendations:
+/// - Denoise radius grows with σ (0 below `NOISE_FLOOR`). For NLM,
+///   h = `NLM_H_PER_SIGMA`·σ and the search window widens with σ, since
+///   heavier noise needs more similar patches to average
+/// - CLAHE clip rises as contrast falls below `TARGET_CONTRAST`, damped by
+///   noise (CLAHE amplifies it); grid follows the image size
+/// - Sharpening rises as sharpness falls below `TARGET_SHARPNESS`, damped by
+///   noise (sharpening amplifies it too)
+pub fn recommend(stats: &ImageStats, w: usize, h: usize) -> Recommendation {
+    let sigma = stats.noise_sigma;
+    let denoise_radius = if sigma < NOISE_FLOOR {
+        0
+    } else if sigma < 4.0 {
+        2
+    } else if sigma < 8.0 {
+        3
+    } else {
+        5
+    };
+    let (nlm_h, nlm_search_radius) = if sigma < NOISE_FLOOR {
+        (0.0, 0)
+    } else {
+        (NLM_H_PER_SIGMA * sigma, if sigma < 8.0 { 5 } else { 7 })
+    };
+
+    let noise_damp = 1.0 / (1.0 + sigma / 8.0);
+    let deficit = (1.0 - stats.contrast / TARGET_CONTRAST).clamp(0.0, 1.0);
+    let clip = 1.0 + (MAX_CLAHE_CLIP - 1.0) * deficit * noise_damp;
+    // pre_process skips CLAHE at clip ≤ 1
+    let clahe_clip = if clip < 1.05 { 1.0 } else { clip };
+    let clahe_grid = (w.min(h) / CLAHE_TILE_PX).clamp(2, 8) as u32;
+
+    let blur_deficit = (1.0 - stats.sharpness / TARGET_SHARPNESS).clamp(0.0, 1.0);
+    let sharpen_strength = MAX_SHARPEN * blur_deficit / (1.0 + sigma / 4.0);
+
+    Recommendation {
+        denoise_radius,
+        nlm_h,
+        nlm_search_radius,
+        clahe_clip,
+        clahe_grid,
+        sharpen_strength,
+    }
+}
+
+/// σ = median |HH| / 0.6745 over non-overlapping 2×2 blocks.
+fn estimate_noise(lum: &[f32], w: usize, h: usize) -> f32 {
+    let mut hh: Vec<f32> = Vec::with_capacity((w / 2) * (h / 2));
+    for y in (0..h.saturating_sub(1)).step_by(2) {
+        for x in (0..w.saturating_sub(1)).step_by(2) {
+            let i = y * w + x;
+            let (a, b, c, d) = (lum[i], lum[i + 1], lum[i + w], lum[i + w + 1]);
+            hh.push(((a - b - c + d) * 0.5).abs());
+        }
+    }
+    if hh.is_empty() {
+        return 0.0;
+    }
+    let mid = hh.len() / 2;
+    let (_, median, _) = hh.select_nth_unstable_by(mid, f32::total_cmp);
+    *median * MAD_TO_SIGMA
+}
+
+/// Mean squared forward-difference gradient.
+fn gradient_energy(lum: &[f32], w: usize, h: usize) -> f32 {
+    if w < 2 || h < 2 {
+        return 0.0;
+    }
+    let mut sum = 0.0f64;
+    for y in 0..h - 1 {
+        for x in 0..w - 1 {
+            let i = y * w + x;
+            let dx = lum[i + 1] - lum[i];
+            let dy = lum[i + w] - lum[i];
+            sum += (dx * dx + dy * dy) as f64;
+        }
+    }
+    (sum / ((w - 1) * (h - 1)) as f64) as f32
+}
+
+/// 3×3 box blur with clamped borders.
+fn box3(lum: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let mut tmp = vec![0.0f32; lum.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let l = x.saturating_sub(1);
+            let r = (x + 1).min(w - 1);
+            tmp[y * w + x] = (lum[y * w + l] + lum[y * w + x] + lum[y * w + r]) / 3.0;
+        }
+    }
+    let mut out = vec![0.0f32; lum.len()];
+    for y in 0..h {
+        let u = y.saturating_sub(1);
+        let d = (y + 1).min(h - 1);
+        for x in 0..w {
+            out[y * w + x] = (tmp[u * w + x] + tmp[y * w + x] + tmp[d * w + x]) / 3.0;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray image of `v(x, y)` plus Gaussian noise of standard deviation
+    /// `sigma` (Box–Muller over an LCG), rounded to 8 bits.
+    fn noisy(w: usize, h: usize, sigma: f32, v: impl Fn(usize, usize) -> f32) -> Vec<u8> {
+        let mut state = 2024u32;
+        let mut uniform = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((state >> 8) as f32 + 0.5) / (1u32 << 24) as f32
+        };
+        let mut rgba = Vec::with_capacity(w * h * 4);
+        for y in 0..h {
+            for x in 0..w {
+                let (u1, u2) = (uniform(), uniform());
+                let n = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+                let g = (v(x, y) + sigma * n).round().clamp(0.0, 255.0) as u8;
+                rgba.extend_from_slice(&[g, g, g, 255]);
+            }
+        }
+        rgba
+    }
+
+    fn stats(noise_sigma: f32, contrast: f32, sharpness: f32) -> ImageStats {
+        ImageStats {
+            noise_sigma,
+            contrast,
+            sharpness,
+        }
+    }
+
+    #[test]
+    fn test_noise_estimate_matches_known_sigma() {
+        for sigma in [3.0, 6.0, 12.0] {
+            let rgba = noisy(128, 128, sigma, |_, _| 128.0);
+            let est = analyze(&rgba, 128, 128).noise_sigma;
+            assert!((est / sigma - 1.0).abs() < 0.1, "σ {sigma}: {est}");
+        }
+    }
+
+    #[test]
+    fn test_noise_estimate_ignores_smooth_content_and_edges() {
+        // Diagonal ramp with a hard vertical edge, no noise
+        let rgba = noisy(128, 128, 0.0, |x, y| {
+            (x + y) as f32 - if x < 64 { 0.0 } else { 60.0 }
+        });
+        assert!(analyze(&rgba, 128, 128).noise_sigma < 0.5);
+        // The same with σ = 5 on top
+        let rgba = noisy(128, 128, 5.0, |x, y| {
+            (x + y) as f32 - if x < 64 { 0.0 } else { 60.0 }
+        });
+        let est = analyze(&rgba, 128, 128).noise_sigma;
+        assert!((est - 5.0).abs() < 0.6, "{est}");
+    }
+
+    #[test]
+    fn test_sharpness_of_crisp_and_soft_edges() {
+        let step = |x: usize, _: usize| if x < 32 { 40.0 } else { 200.0 };
+        let crisp = analyze(&noisy(64, 64, 0.0, step), 64, 64).sharpness;
+        assert!((crisp - 2.0 / 3.0).abs() < 0.05, "{crisp}");
+        let ramp = |x: usize, _: usize| 40.0 + 160.0 * ((x as f32 - 24.0) / 16.0).clamp(0.0, 1.0);
+        let soft = analyze(&noisy(64, 64, 0.0, ramp), 64, 64).sharpness;
+        assert!(soft < 0.1, "{soft}");
+    }
+
+    #[test]
+    fn test_denoise_thresholds() {
+        let radius = |sigma| recommend(&stats(sigma, 0.3, 0.6), 512, 512).denoise_radius;
+        assert_eq!(radius(1.0), 0);
+        assert_eq!(radius(NOISE_FLOOR), 2);
+        assert_eq!(radius(4.0), 3);
+        assert_eq!(radius(8.0), 5);
+
+        let clean = recommend(&stats(1.0, 0.3, 0.6), 512, 512);
+        assert_eq!((clean.nlm_h, clean.nlm_search_radius), (0.0, 0));
+        let noisy = recommend(&stats(10.0, 0.3, 0.6), 512, 512);
+        assert!((noisy.nlm_h - NLM_H_PER_SIGMA * 10.0).abs() < 1e-4);
+        assert_eq!(noisy.nlm_search_radius, 7);
+    }
+
+    #[test]
+    fn test_clahe_and_sharpen_thresholds() {
+        // Enough contrast and sharpness: CLAHE and sharpening stay off
+        let good = recommend(&stats(0.0, TARGET_CONTRAST, TARGET_SHARPNESS), 1024, 768);
+        assert_eq!(good.clahe_clip, 1.0);
+        assert_eq!(good.sharpen_strength, 0.0);
+        assert_eq!(good.clahe_grid, 6);
+
+        // Flat and soft: full clip and sharpening on a clean image
+        let flat = recommend(&stats(0.0, 0.0, 0.0), 100, 100);
+        assert!((flat.clahe_clip - MAX_CLAHE_CLIP).abs() < 1e-4);
+        assert!((flat.sharpen_strength - MAX_SHARPEN).abs() < 1e-4);
+        assert_eq!(flat.clahe_grid, 2);
+
+        // Noise damps both
+        let grainy = recommend(&stats(8.0, 0.0, 0.0), 100, 100);
+        assert!(grainy.clahe_clip < flat.clahe_clip);
+        assert!(grainy.sharpen_strength < flat.sharpen_strength / 2.0);
+    }
+}
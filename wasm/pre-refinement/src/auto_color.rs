@@ -0,0 +1,328 @@
+use imaging::srgb;
+
+const MAX_GAIN: f32 = 2.0;
+const BRIGHT_FRACTION: f32 = 0.01;
+/// Channel value treated as clipped by the sensor.
+const CLIPPED: u8 = 254;
+/// Minimum stretched input range in levels; flatter images are left alone.
+const MIN_RANGE: usize = 8;
+const OPAQUE: u8 = 128;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WhiteBalance {
+    None,
+    GrayWorld,
+    WhitePatch,
+    Combined,
+}
+
+impl WhiteBalance {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "gray_world" => WhiteBalance::GrayWorld,
+            "white_patch" => WhiteBalance::WhitePatch,
+            "combined" => WhiteBalance::Combined,
+            _ => WhiteBalance::None,
+        }
+    }
+}
+
+#[inline]
+fn luminance(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+/// Gains that map `reference` (linear RGB) to a gray of the same luminance.
+fn gains_for(reference: [f32; 3]) -> [f32; 3] {
+    let y = luminance(reference);
+    let mut g = [1.0f32; 3];
+    for c in 0..3 {
+        if reference[c] > 1e-6 && y > 1e-6 {
+            g[c] = (y / reference[c]).clamp(1.0 / MAX_GAIN, MAX_GAIN);
+        }
+    }
+    g
+}
+
+/// Per-channel white balance gains for `method`, to be applied in linear light.
+///
+/// The correction is von Kries diagonal: one gain per channel. Gains are
+/// normalized so the reference color keeps its luminance, and clamped to
+/// [1/`MAX_GAIN`, `MAX_GAIN`].
+/// - GrayWorld: the average scene color is gray (Buchsbaum, 1980)
+/// - WhitePatch: the brightest `BRIGHT_FRACTION` of pixels are white
+///   (Land's retinex max-RGB, made robust by averaging a percentile instead
+///   of taking the single maximum; clipped pixels are skipped)
+/// - Combined: geometric mean of both gain sets — gray-world fails on scenes
+///   dominated by one color, white-patch on scenes without a true white;
+///   together they rarely both fail in the same direction
+///
+/// Transparent pixels (alpha < 128) are ignored by all statistics, so
+/// cutouts are measured on the subject only.
+pub fn white_balance_gains(rgba: &[u8], method: WhiteBalance) -> [f32; 3] {
+    let lut = srgb::build_srgb_to_linear_lut();
+    let opaque = || rgba.chunks_exact(4).filter(|p| p[3] >= OPAQUE);
+
+    let gray_world = || {
+        let mut sum = [0.0f64; 3];
+        let mut n = 0usize;
+        for p in opaque() {
+            if p[0] >= CLIPPED || p[1] >= CLIPPED || p[2] >= CLIPPED {
+                continue;
+            }
+            for c in 0..3 {
+                sum[c] += lut[p[c] as usize] as f64;
+            }
+            n += 1;
+        }
+        if n == 0 {
+            return [1.0f32; 3];
+        }
+        gains_for([
+            (sum[0] / n as f64) as f32,
+            (sum[1] / n as f64) as f32,
+            (sum[2] / n as f64) as f32,
+        ])
+    };
+
+    let white_patch = || {
+        // Luma threshold of the brightest fraction among unclipped pixels
+        let luma = |p: &[u8]| (p[0] as u32 * 54 + p[1] as u32 * 183 + p[2] as u32 * 19) >> 8;
+        let unclipped = || opaque().filter(|p| p[0] < CLIPPED && p[1] < CLIPPED && p[2] < CLIPPED);
+        let mut hist = [0usize; 256];
+        let mut total = 0usize;
+        for p in unclipped() {
+            hist[luma(p) as usize] += 1;
+            total += 1;
+        }
+        if total == 0 {
+            return [1.0f32; 3];
+        }
+        let wanted = ((total as f32 * BRIGHT_FRACTION).ceil() as usize).max(1);
+        let mut threshold = 255u32;
+        let mut acc = 0usize;
+        for level in (0..256).rev() {
+            acc += hist[level];
+            threshold = level as u32;
+            if acc >= wanted {
+                break;
+            }
+        }
+
+        let mut sum = [0.0f64; 3];
+        let mut n = 0usize;
+        for p in unclipped().filter(|p| luma(p) >= threshold) {
+            for c in 0..3 {
+                sum[c] += lut[p[c] as usize] as f64;
+            }
+            n += 1;
+        }
+        gains_for([
+            (sum[0] / n as f64) as f32,
+            (sum[1] / n as f64) as f32,
+            (sum[2] / n as f64) as f32,
+        ])
+    };
+
+    match method {
+        WhiteBalance::None => [1.0; 3],
+        WhiteBalance::GrayWorld => gray_world(),
+        WhiteBalance::WhitePatch => white_patch(),
+        WhiteBalance::Combined => {
+            let a = gray_world();
+            let b = white_patch();
+            [
+                (a[0] * b[0]).sqrt(),
+                (a[1] * b[1]).sqrt(),
+                (a[2] * b[2]).sqrt(),
+            ]
+        }
+    }
+}
+
+/// White-balance `rgba` in place. `strength` (0–1) interpolates the gains
+/// geometrically from 1.
+pub fn apply_white_balance(rgba: &mut [u8], method: WhiteBalance, strength: f32) {
+    let strength = strength.clamp(0.0, 1.0);
+    if method == WhiteBalance::None || strength == 0.0 {
+        return;
+    }
+    let gains = white_balance_gains(rgba, method);
+    let lut = srgb::build_srgb_to_linear_lut();
+
+    let mut channel_lut = [[0u8; 256]; 3];
+    for (c, table) in channel_lut.iter_mut().enumerate() {
+        let g = gains[c].powf(strength);
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = srgb::linear_to_srgb(lut[i] * g);
+        }
+    }
+    for px in rgba.chunks_exact_mut(4) {
+        for c in 0..3 {
+            px[c] = channel_lut[c][px[c] as usize];
+        }
+    }
+}
+
+/// Auto levels: stretch the [clip, 100 − clip] percentile range
+/// (`clip_percent`) to 0–255, on luma (one mapping for all channels, no new
+/// cast) or, with `per_channel`, on each channel separately (also removes
+/// casts, like "Auto Color"). Pixels with alpha < 128 are not counted.
+pub fn apply_auto_levels(rgba: &mut [u8], clip_percent: f32, per_channel: bool) {
+    let clip = (clip_percent.max(0.0) / 100.0).min(0.49);
+
+    let mut hists = [[0usize; 256]; 3];
+    let mut total = 0usize;
+    for p in rgba.chunks_exact(4).filter(|p| p[3] >= OPAQUE) {
+        if per_channel {
+            for c in 0..3 {
+                hists[c][p[c] as usize] += 1;
+            }
+        } else {
+            let y = (p[0] as u32 * 54 + p[1] as u32 * 183 + p[2] as u32 * 19 + 128) >> 8;
+            hists[0][y as usize] += 1;
+        }
+        total += 1;
+    }
+    if total == 0 {
+        return;
+    }
+
+    let skip = (total as f32 * clip) as usize;
+    let bounds = |hist: &[usize; 256]| -> Option<(usize, usize)> {
+        let mut acc = 0usize;
+        let mut lo = 0usize;
+        for (level, &count) in hist.iter().enumerate() {
+            acc += count;
+            if acc > skip {
+                lo = level;
+                break;
+            }
+        }
+        acc = 0;
+        let mut hi = 255usize;
+        for (level, &count) in hist.iter().enumerate().rev() {
+            acc += count;
+            if acc > skip {
+                hi = level;
+                break;
+            }
+        }
+        (hi >= lo + MIN_RANGE).then_some((lo, hi))
+    };
+
+    let stretch = |(lo, hi): (usize, usize)| {
+        let mut table = [0u8; 256];
+        let scale = 255.0 / (hi - lo) as f32;
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = ((i as f32 - lo as f32) * scale + 0.5).clamp(0.0, 255.0) as u8;
+        }
+        table
+    };
+
+    let identity = {
+        let mut t = [0u8; 256];
+        for (i, v) in t.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        t
+    };
+    let tables: [[u8; 256]; 3] = if per_channel {
+        [
+            bounds(&hists[0]).map_or(identity, stretch),
+            bounds(&hists[1]).map_or(identity, stretch),
+            bounds(&hists[2]).map_or(identity, stretch),
+        ]
+    } else {
+        match bounds(&hists[0]) {
+            Some(b) => [stretch(b); 3],
+            None => return,
+        }
+    };
+
+    for px in rgba.chunks_exact_mut(4) {
+        for c in 0..3 {
+            px[c] = tables[c][px[c] as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grays from 40 to 200 seen through a cast of `gains` in linear light.
+    fn cast_grays(gains: [f32; 3]) -> Vec<u8> {
+        let lut = srgb::build_srgb_to_linear_lut();
+        (40..200u8)
+            .flat_map(|v| {
+                let l = lut[v as usize];
+                [
+                    srgb::linear_to_srgb(l * gains[0]),
+                    srgb::linear_to_srgb(l * gains[1]),
+                    srgb::linear_to_srgb(l * gains[2]),
+                    255,
+                ]
+            })
+            .collect()
+    }
+
+    fn spread(p: &[u8]) -> u8 {
+        p[0].max(p[1]).max(p[2]) - p[0].min(p[1]).min(p[2])
+    }
+
+    #[test]
+    fn test_gray_world_removes_uniform_cast() {
+        let mut rgba = cast_grays([1.2, 1.0, 0.8]);
+        assert!(rgba.chunks_exact(4).any(|p| spread(p) > 10));
+        apply_white_balance(&mut rgba, WhiteBalance::GrayWorld, 1.0);
+        for p in rgba.chunks_exact(4) {
+            assert!(spread(p) <= 2, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn test_clipped_and_transparent_pixels_are_ignored() {
+        let rgba = cast_grays([1.2, 1.0, 0.8]);
+        let mut extra = rgba.clone();
+        for _ in 0..40 {
+            // Blown highlights and a transparent green backdrop
+            extra.extend_from_slice(&[255, 255, 230, 255]);
+            extra.extend_from_slice(&[0, 255, 0, 0]);
+        }
+        for method in [WhiteBalance::GrayWorld, WhiteBalance::WhitePatch] {
+            let a = white_balance_gains(&rgba, method);
+            let b = white_balance_gains(&extra, method);
+            for c in 0..3 {
+                assert!((a[c] - b[c]).abs() < 1e-4, "{a:?} vs {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_none_and_neutral_image_keep_gains_at_one() {
+        let grays = cast_grays([1.0; 3]);
+        assert_eq!(white_balance_gains(&grays, WhiteBalance::None), [1.0; 3]);
+        for g in white_balance_gains(&grays, WhiteBalance::Combined) {
+            assert!((g - 1.0).abs() < 0.01, "{g}");
+        }
+    }
+
+    #[test]
+    fn test_auto_levels_stretches_low_contrast_ramp() {
+        let mut rgba: Vec<u8> = (100..=150u8).flat_map(|v| [v, v, v, 255]).collect();
+        apply_auto_levels(&mut rgba, 0.0, false);
+        let values: Vec<u8> = rgba.chunks_exact(4).map(|p| p[0]).collect();
+        assert_eq!(values[0], 0);
+        assert_eq!(values[values.len() - 1], 255);
+        assert!(values.windows(2).all(|v| v[1] > v[0]));
+    }
+
+    #[test]
+    fn test_auto_levels_leaves_flat_image_alone() {
+        let rgba: Vec<u8> = (120..124u8).flat_map(|v| [v, v, v, 255]).collect();
+        let mut buf = rgba.clone();
+        apply_auto_levels(&mut buf, 0.0, true);
+        assert_eq!(buf, rgba);
+    }
+}
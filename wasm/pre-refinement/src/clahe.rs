@@ -1,35 +1,205 @@
+use crate::color;
+use imaging::{oklab, srgb};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ClaheSpace {
+    Luma,
+    Oklab,
+    Lab,
+    Hsv,
+}
+
+impl ClaheSpace {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "oklab" => ClaheSpace::Oklab,
+            "lab" => ClaheSpace::Lab,
+            "hsv" => ClaheSpace::Hsv,
+            _ => ClaheSpace::Luma,
+        }
+    }
+}
+
+#[inline(always)]
+fn rgb_to_lum(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+}
+
 /// CLAHE — Contrast Limited Adaptive Histogram Equalization.
 ///
+/// Equalized channel (`ClaheSpace`):
+/// - Luma: fixed-point luma, RGB rescaled by new/old luma (the historical
+///   mode — bright saturated colors can clip and shift hue)
+/// - Oklab / Lab: perceptual lightness; a and b are kept, and colors pushed
+///   out of gamut lose chroma at fixed lightness and hue instead of clipping
+/// - Hsv: V = max(R, G, B); RGB scaled by new/old V, which keeps hue and
+///   saturation exactly and can never clip
+///
+/// The perceptual modes anchor each tile's CDF at level 0, so black stays
+/// black instead of being lifted to gray. `strength` blends the result with
+/// the original (1 = full CLAHE).
+///
 /// Optimizations:
 /// - Single luminance extraction pass (BT.709 fixed-point: 77R+150G+29B >> 8)
 /// - Per-tile histogram build with clip & redistribute
-/// - CDF stored as u8 LUT (no f32 during apply phase)
+/// - CDF stored as a 256-entry LUT per tile
 /// - Bilinear interpolation between tile CDFs using precomputed reciprocals
-/// - Fused RGB rescale via 16.16 fixed-point multiply
-#[inline(always)]
-fn rgb_to_lum(r: u8, g: u8, b: u8) -> u8 {
-    ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+/// - Fused RGB rescale via 16.16 fixed-point multiply (luma mode)
+pub fn apply_clahe(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    clip_limit: f32,
+    grid_size: usize,
+    space: ClaheSpace,
+    strength: f32,
+) {
+    let strength = strength.clamp(0.0, 1.0);
+    if strength == 0.0 {
+        return;
+    }
+    let original = (strength < 1.0).then(|| rgba.to_vec());
+
+    match space {
+        ClaheSpace::Luma => apply_luma(rgba, w, h, clip_limit, grid_size),
+        ClaheSpace::Hsv => apply_hsv(rgba, w, h, clip_limit, grid_size),
+        ClaheSpace::Oklab => apply_lightness(
+            rgba,
+            w,
+            h,
+            clip_limit,
+            grid_size,
+            1.0,
+            oklab::linear_to_oklab,
+            oklab::oklab_to_linear,
+        ),
+        ClaheSpace::Lab => apply_lightness(
+            rgba,
+            w,
+            h,
+            clip_limit,
+            grid_size,
+            100.0,
+            color::linear_to_lab,
+            color::lab_to_linear,
+        ),
+    }
+
+    if let Some(original) = original {
+        for (px, src) in rgba.chunks_exact_mut(4).zip(original.chunks_exact(4)) {
+            for c in 0..3 {
+                let a = src[c] as f32;
+                px[c] = (a + strength * (px[c] as f32 - a) + 0.5) as u8;
+            }
+        }
+    }
 }
 
-pub fn apply_clahe(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_size: usize) {
+fn apply_luma(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_size: usize) {
+    // === Pass 1: Extract luminance ===
+    let lum: Vec<u8> = rgba
+        .chunks_exact(4)
+        .map(|p| rgb_to_lum(p[0], p[1], p[2]))
+        .collect();
+
+    // === Pass 2 + 3: Tile CDFs, interpolated per pixel ===
+    let equalized = equalize(&lum, w, h, clip_limit, grid_size, false);
+
+    for (idx, &new_lum) in equalized.iter().enumerate() {
+        let new_lum_u8 = new_lum as u8;
+        let off = idx * 4;
+        let old_lum = lum[idx];
+        if old_lum > 0 {
+            let scale_fp = ((new_lum_u8 as u32) << 16) / old_lum as u32;
+            rgba[off] = ((rgba[off] as u32 * scale_fp) >> 16).min(255) as u8;
+            rgba[off + 1] = ((rgba[off + 1] as u32 * scale_fp) >> 16).min(255) as u8;
+            rgba[off + 2] = ((rgba[off + 2] as u32 * scale_fp) >> 16).min(255) as u8;
+        } else if new_lum_u8 > 0 {
+            rgba[off] = new_lum_u8;
+            rgba[off + 1] = new_lum_u8;
+            rgba[off + 2] = new_lum_u8;
+        }
+    }
+}
+
+fn apply_hsv(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_size: usize) {
+    let value: Vec<u8> = rgba
+        .chunks_exact(4)
+        .map(|p| p[0].max(p[1]).max(p[2]))
+        .collect();
+    let equalized = equalize(&value, w, h, clip_limit, grid_size, true);
+
+    for ((px, &v), &new_v) in rgba.chunks_exact_mut(4).zip(&value).zip(&equalized) {
+        if v == 0 {
+            continue;
+        }
+        let scale = new_v / v as f32;
+        for c in px.iter_mut().take(3) {
+            *c = (*c as f32 * scale + 0.5).min(255.0) as u8;
+        }
+    }
+}
+
+/// CLAHE on the lightness of a Lab-like space whose L spans [0, `l_max`].
+///
+/// The histogram uses L quantized to 256 levels; each pixel then moves by
+/// the equalization delta of its level, so sub-level detail survives.
+#[allow(clippy::too_many_arguments)]
+fn apply_lightness(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    clip_limit: f32,
+    grid_size: usize,
+    l_max: f32,
+    to_lab: fn([f32; 3]) -> [f32; 3],
+    to_linear: fn([f32; 3]) -> [f32; 3],
+) {
+    let lut = srgb::build_srgb_to_linear_lut();
+    let labs: Vec<[f32; 3]> = rgba
+        .chunks_exact(4)
+        .map(|p| to_lab([lut[p[0] as usize], lut[p[1] as usize], lut[p[2] as usize]]))
+        .collect();
+    let to_level = 255.0 / l_max;
+    let levels: Vec<u8> = labs
+        .iter()
+        .map(|lab| (lab[0] * to_level + 0.5).clamp(0.0, 255.0) as u8)
+        .collect();
+    let equalized = equalize(&levels, w, h, clip_limit, grid_size, true);
+
+    for (i, px) in rgba.chunks_exact_mut(4).enumerate() {
+        let lab = labs[i];
+        let delta = (equalized[i] - levels[i] as f32) / to_level;
+        let l = (lab[0] + delta).clamp(0.0, l_max);
+        let rgb = oklab::fit_chroma([l, lab[1], lab[2]], to_linear);
+        px[0] = srgb::linear_to_srgb(rgb[0]);
+        px[1] = srgb::linear_to_srgb(rgb[1]);
+        px[2] = srgb::linear_to_srgb(rgb[2]);
+    }
+}
+
+/// Per-tile clipped-histogram CDFs, bilinearly interpolated per pixel.
+///
+/// Returns the equalized level (0–255) of every pixel. `anchor_black` maps
+/// level 0 to 0 in every tile; otherwise LUT entries are truncated to whole
+/// levels, as the luma mode has always done.
+fn equalize(
+    lum: &[u8],
+    w: usize,
+    h: usize,
+    clip_limit: f32,
+    grid_size: usize,
+    anchor_black: bool,
+) -> Vec<f32> {
     let grid = grid_size.max(2);
     let tile_w = w.div_ceil(grid);
     let tile_h = h.div_ceil(grid);
-    let npx = w * h;
     let ntx = grid;
     let nty = grid;
 
-    // === Pass 1: Extract luminance ===
-    let mut lum = vec![0u8; npx];
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..npx {
-        let off = i * 4;
-        lum[i] = rgb_to_lum(rgba[off], rgba[off + 1], rgba[off + 2]);
-    }
-
-    // === Pass 2: Build per-tile CDF LUTs ===
+    // === Build per-tile CDF LUTs ===
     let num_tiles = ntx * nty;
-    let mut cdf_lut: Vec<[u8; 256]> = vec![[0u8; 256]; num_tiles];
+    let mut cdf_lut: Vec<[f32; 256]> = vec![[0.0f32; 256]; num_tiles];
 
     for ty in 0..nty {
         for tx in 0..ntx {
@@ -49,11 +219,10 @@ pub fn apply_clahe(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_si
                 }
             }
 
+            let tile_idx = ty * ntx + tx;
             if count == 0 {
-                let lut = &mut cdf_lut[ty * ntx + tx];
-                #[allow(clippy::needless_range_loop)]
-                for i in 0..256 {
-                    lut[i] = i as u8;
+                for (i, v) in cdf_lut[tile_idx].iter_mut().enumerate() {
+                    *v = i as f32;
                 }
                 continue;
             }
@@ -76,20 +245,22 @@ pub fn apply_clahe(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_si
                 }
             }
 
-            // Build CDF as u8 LUT
-            let inv_count = 255.0 / count as f32;
+            // Build CDF LUT
+            let base = if anchor_black { hist[0] } else { 0 };
+            let inv_count = 255.0 / (count - base).max(1) as f32;
             let mut cumulative = 0u32;
-            let tile_idx = ty * ntx + tx;
-            for i in 0..256 {
-                cumulative += hist[i];
-                cdf_lut[tile_idx][i] = (cumulative as f32 * inv_count).min(255.0) as u8;
+            for (i, &bin) in hist.iter().enumerate() {
+                cumulative += bin;
+                let v = ((cumulative - base) as f32 * inv_count).min(255.0);
+                cdf_lut[tile_idx][i] = if anchor_black { v } else { v.floor() };
             }
         }
     }
 
-    // === Pass 3: Apply with bilinear interpolation ===
+    // === Apply with bilinear interpolation ===
     let inv_tile_w = 1.0 / tile_w as f32;
     let inv_tile_h = 1.0 / tile_h as f32;
+    let mut out = vec![0.0f32; w * h];
 
     for y in 0..h {
         let fy = (y as f32 + 0.5) * inv_tile_h - 0.5;
@@ -111,26 +282,106 @@ pub fn apply_clahe(rgba: &mut [u8], w: usize, h: usize, clip_limit: f32, grid_si
             let idx = y * w + x;
             let l = lum[idx] as usize;
 
-            // Bilinear from u8 LUTs
-            let c00 = cdf_lut[row0 + tx0][l] as f32;
-            let c10 = cdf_lut[row0 + tx1][l] as f32;
-            let c01 = cdf_lut[row1 + tx0][l] as f32;
-            let c11 = cdf_lut[row1 + tx1][l] as f32;
-
-            let new_lum = (c00 * wx_inv + c10 * wx) * wy_inv + (c01 * wx_inv + c11 * wx) * wy;
-            let new_lum_u8 = new_lum as u8;
-
-            let off = idx * 4;
-            let old_lum = lum[idx];
-            if old_lum > 0 {
-                let scale_fp = ((new_lum_u8 as u32) << 16) / old_lum as u32;
-                rgba[off] = ((rgba[off] as u32 * scale_fp) >> 16).min(255) as u8;
-                rgba[off + 1] = ((rgba[off + 1] as u32 * scale_fp) >> 16).min(255) as u8;
-                rgba[off + 2] = ((rgba[off + 2] as u32 * scale_fp) >> 16).min(255) as u8;
-            } else if new_lum_u8 > 0 {
-                rgba[off] = new_lum_u8;
-                rgba[off + 1] = new_lum_u8;
-                rgba[off + 2] = new_lum_u8;
+            let c00 = cdf_lut[row0 + tx0][l];
+            let c10 = cdf_lut[row0 + tx1][l];
+            let c01 = cdf_lut[row1 + tx0][l];
+            let c11 = cdf_lut[row1 + tx1][l];
+
+            out[idx] = (c00 * wx_inv + c10 * wx) * wy_inv + (c01 * wx_inv + c11 * wx) * wy;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERCEPTUAL: [ClaheSpace; 3] = [ClaheSpace::Oklab, ClaheSpace::Lab, ClaheSpace::Hsv];
+
+    /// Low-contrast 40×30 test image from a per-pixel color.
+    fn image(px: impl Fn(usize, usize) -> [u8; 3]) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(40 * 30 * 4);
+        for y in 0..30 {
+            for x in 0..40 {
+                let [r, g, b] = px(x, y);
+                rgba.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        rgba
+    }
+
+    /// HSV hue in degrees.
+    fn hue(p: &[u8]) -> f32 {
+        let [r, g, b] = [p[0] as f32, p[1] as f32, p[2] as f32];
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let d = max - min;
+        let h = if max == r {
+            (g - b) / d
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        (h * 60.0).rem_euclid(360.0)
+    }
+
+    #[test]
+    fn test_luma_matches_baseline() {
+        // Pins the historical luma path: FNV-1a of the output of the original
+        // implementation on this image
+        let mut rgba = image(|x, y| {
+            [
+                (60 + (x * 5 + y * 2) % 90) as u8,
+                (50 + (x * 3 + y * 7) % 100) as u8,
+                (70 + (x * y) % 80) as u8,
+            ]
+        });
+        apply_clahe(&mut rgba, 40, 30, 2.0, 4, ClaheSpace::Luma, 1.0);
+        let hash = rgba.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        assert_eq!(hash, 0xe008_eed3_d933_0dbd);
+    }
+
+    #[test]
+    fn test_neutral_gray_stays_neutral() {
+        for space in PERCEPTUAL {
+            let mut rgba = image(|x, y| {
+                let v = (90 + x + y) as u8;
+                [v, v, v]
+            });
+            let before = rgba.clone();
+            apply_clahe(&mut rgba, 40, 30, 3.0, 4, space, 1.0);
+            assert_ne!(rgba, before);
+            for px in rgba.chunks_exact(4) {
+                let spread = px[0].max(px[1]).max(px[2]) - px[0].min(px[1]).min(px[2]);
+                assert!(spread <= 1, "{px:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hue_is_kept() {
+        for space in PERCEPTUAL {
+            // Muted orange and teal patches with a gentle brightness ramp
+            let mut rgba = image(|x, y| {
+                let v = (x + y) as u8;
+                if x < 20 {
+                    [120 + v, 90 + v, 60 + v]
+                } else {
+                    [50 + v, 100 + v, 110 + v]
+                }
+            });
+            let before = rgba.clone();
+            apply_clahe(&mut rgba, 40, 30, 3.0, 4, space, 1.0);
+            for (a, b) in before.chunks_exact(4).zip(rgba.chunks_exact(4)) {
+                let chroma = b[0].max(b[1]).max(b[2]) - b[0].min(b[1]).min(b[2]);
+                if chroma < 20 {
+                    continue;
+                }
+                let dh = (hue(a) - hue(b)).abs();
+                assert!(dh.min(360.0 - dh) < 6.0, "{a:?} → {b:?}");
             }
         }
     }
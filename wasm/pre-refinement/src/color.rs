@@ -0,0 +1,45 @@
+// D65 reference white
+const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+
+/// CIELAB (D65 white, L* in [0, 100]) of linear-light RGB (0–1). Oklab and
+/// gamut fitting live in `imaging::oklab`.
+pub fn linear_to_lab(c: [f32; 3]) -> [f32; 3] {
+    let x = 0.412_456_4 * c[0] + 0.357_576_1 * c[1] + 0.180_437_5 * c[2];
+    let y = 0.212_672_9 * c[0] + 0.715_152_2 * c[1] + 0.072_175 * c[2];
+    let z = 0.019_333_9 * c[0] + 0.119_192 * c[1] + 0.950_304_1 * c[2];
+    let f = |t: f32| {
+        if t > LAB_EPSILON {
+            t.cbrt()
+        } else {
+            (LAB_KAPPA * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / WHITE[0]), f(y / WHITE[1]), f(z / WHITE[2]));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn lab_to_linear(lab: [f32; 3]) -> [f32; 3] {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    let finv = |f: f32| {
+        let t = f * f * f;
+        if t > LAB_EPSILON {
+            t
+        } else {
+            (116.0 * f - 16.0) / LAB_KAPPA
+        }
+    };
+    let (x, y, z) = (
+        finv(fx) * WHITE[0],
+        finv(fy) * WHITE[1],
+        finv(fz) * WHITE[2],
+    );
+    [
+        3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+        -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z,
+        0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+    ]
+}
@@ -1,128 +1,302 @@
+const RANGE_LUT_LEN: usize = 443; // ceil(255·√3) + 1
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RangeMetric {
+    Max,
+    Euclidean,
+    Luma,
+}
+
+impl RangeMetric {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "euclidean" => RangeMetric::Euclidean,
+            "luma" => RangeMetric::Luma,
+            _ => RangeMetric::Max,
+        }
+    }
+
+    /// Integer range distance, an index into the range LUT.
+    #[inline(always)]
+    fn distance(self, a: [i32; 3], b: [i32; 3]) -> usize {
+        let dr = a[0] - b[0];
+        let dg = a[1] - b[1];
+        let db = a[2] - b[2];
+        match self {
+            RangeMetric::Max => dr
+                .unsigned_abs()
+                .max(dg.unsigned_abs())
+                .max(db.unsigned_abs()) as usize,
+            RangeMetric::Euclidean => {
+                (((dr * dr + dg * dg + db * db) as f32).sqrt() + 0.5) as usize
+            }
+            // BT.709 weights in 1/1024ths
+            RangeMetric::Luma => ((218 * dr + 732 * dg + 74 * db).unsigned_abs() >> 10) as usize,
+        }
+    }
+}
+
+/// Effective radius and spatial sigma: `sigma_s` = 0 follows the capped radius.
+fn resolve_radius(radius: usize, radius_cap: usize, sigma_s: f32) -> (usize, f32) {
+    let r = radius.min(radius_cap.max(1));
+    let sigma_s = if sigma_s > 0.0 { sigma_s } else { r as f32 };
+    (r, sigma_s)
+}
+
+/// Range weights exp(−d²/(2σr²)) for every integer distance.
+fn range_lut(sigma_r: f32) -> Vec<f32> {
+    let sigma_r = sigma_r.max(0.1);
+    let inv_2sr = -0.5 / (sigma_r * sigma_r);
+    (0..RANGE_LUT_LEN)
+        .map(|d| (inv_2sr * (d * d) as f32).exp())
+        .collect()
+}
+
 /// Separable Bilateral Filter — O(w*h*r) instead of O(w*h*r²).
 ///
 /// Key insight from Paris & Durand: a bilateral filter can be approximated
 /// by two 1D passes (horizontal then vertical) with range-weighted kernels.
 /// This gives 2*r work per pixel instead of (2r+1)² — up to 10x faster for r=5.
+/// The approximation can streak along diagonal edges; `bilateral_full` is the
+/// exact (2r+1)² filter for those cases.
+///
+/// Range distance between two pixels (`RangeMetric`):
+/// - Max: largest per-channel difference (robust, the historical default)
+/// - Euclidean: RGB distance, smoother falloff on colored edges
+/// - Luma: BT.709 luma difference only — chroma noise gets averaged freely
 ///
 /// Optimizations:
 /// - Precomputed spatial weight LUT (no exp() in hot loop)
-/// - Precomputed range weight LUT (256 entries, covers all u8 diffs)
+/// - Precomputed range weight LUT (443 entries, covers every integer distance)
 /// - Two-pass separable: horizontal then vertical
 /// - Process R,G,B together per neighbor (cache-friendly)
 /// - Reciprocal multiply for normalization
-pub fn bilateral_separable(rgba: &mut [u8], w: usize, h: usize, radius: usize) {
-    let r = radius.min(7); // Cap for WASM perf
-    let sigma_s = r as f32;
-    let sigma_r = 30.0f32;
+#[allow(clippy::too_many_arguments)]
+pub fn bilateral_separable(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    radius: usize,
+    sigma_s: f32,
+    sigma_r: f32,
+    metric: RangeMetric,
+    radius_cap: usize,
+) {
+    let (r, sigma_s) = resolve_radius(radius, radius_cap, sigma_s);
 
     // Precompute spatial weights for 1D kernel: exp(-d²/(2σ²))
     let kernel_len = 2 * r + 1;
     let inv_2ss = -0.5 / (sigma_s * sigma_s);
-    let mut spatial_w = vec![0.0f32; kernel_len];
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..kernel_len {
-        let d = i as f32 - r as f32;
-        spatial_w[i] = (inv_2ss * d * d).exp();
-    }
-
-    // Precompute range weights: exp(-diff²/(2σr²)) for diff in 0..256
-    let inv_2sr = -0.5 / (sigma_r * sigma_r);
-    let mut range_w = [0.0f32; 256];
-    for d in 0..256u32 {
-        range_w[d as usize] = (inv_2sr * (d * d) as f32).exp();
-    }
+    let spatial_w: Vec<f32> = (0..kernel_len)
+        .map(|i| {
+            let d = i as f32 - r as f32;
+            (inv_2ss * d * d).exp()
+        })
+        .collect();
+    let range_w = range_lut(sigma_r);
 
     // --- Pass 1: Horizontal ---
     let mut tmp = rgba.to_vec();
-    let ri = r as isize;
+    bilateral_pass(rgba, &mut tmp, w, h, 1, &spatial_w, &range_w, metric);
+
+    // --- Pass 2: Vertical (read from tmp, write to rgba) ---
+    bilateral_pass(&tmp, rgba, w, h, w, &spatial_w, &range_w, metric);
+}
+
+/// One 1D pass along `stride` (1 = rows, w = columns), reading `src`.
+#[allow(clippy::too_many_arguments)]
+fn bilateral_pass(
+    src: &[u8],
+    dst: &mut [u8],
+    w: usize,
+    h: usize,
+    stride: usize,
+    spatial_w: &[f32],
+    range_w: &[f32],
+    metric: RangeMetric,
+) {
+    let ri = (spatial_w.len() / 2) as isize;
+    let horizontal = stride == 1;
 
     for y in 0..h {
-        let row = y * w;
         for x in 0..w {
-            let ci = (row + x) * 4;
-            let cr = rgba[ci] as i32;
-            let cg = rgba[ci + 1] as i32;
-            let cb = rgba[ci + 2] as i32;
+            let idx = y * w + x;
+            let ci = idx * 4;
+            let c = [src[ci] as i32, src[ci + 1] as i32, src[ci + 2] as i32];
+            let pos = if horizontal { x } else { y } as isize;
+            let len = if horizontal { w } else { h } as isize;
 
             let mut sr = 0.0f32;
             let mut sg = 0.0f32;
             let mut sb = 0.0f32;
             let mut sw = 0.0f32;
 
-            #[allow(clippy::needless_range_loop)]
-            for ki in 0..kernel_len {
-                let sx = x as isize + ki as isize - ri;
-                if sx < 0 || sx >= w as isize {
+            for (ki, &spatial) in spatial_w.iter().enumerate() {
+                let offset = ki as isize - ri;
+                let s = pos + offset;
+                if s < 0 || s >= len {
                     continue;
                 }
-                let ni = (row + sx as usize) * 4;
-                let nr = rgba[ni] as i32;
-                let ng = rgba[ni + 1] as i32;
-                let nb = rgba[ni + 2] as i32;
-
-                let diff = (cr - nr)
-                    .unsigned_abs()
-                    .max((cg - ng).unsigned_abs())
-                    .max((cb - nb).unsigned_abs()) as usize;
-
-                let wt = spatial_w[ki] * range_w[diff.min(255)];
-                sr += nr as f32 * wt;
-                sg += ng as f32 * wt;
-                sb += nb as f32 * wt;
+                let ni = (idx as isize + offset * stride as isize) as usize * 4;
+                let n = [src[ni] as i32, src[ni + 1] as i32, src[ni + 2] as i32];
+
+                let diff = metric.distance(c, n);
+                let wt = spatial * range_w[diff.min(RANGE_LUT_LEN - 1)];
+                sr += n[0] as f32 * wt;
+                sg += n[1] as f32 * wt;
+                sb += n[2] as f32 * wt;
                 sw += wt;
             }
 
             if sw > 0.0 {
                 let inv = 1.0 / sw;
-                tmp[ci] = (sr * inv) as u8;
-                tmp[ci + 1] = (sg * inv) as u8;
-                tmp[ci + 2] = (sb * inv) as u8;
+                dst[ci] = (sr * inv + 0.5) as u8;
+                dst[ci + 1] = (sg * inv + 0.5) as u8;
+                dst[ci + 2] = (sb * inv + 0.5) as u8;
             }
         }
     }
+}
 
-    // --- Pass 2: Vertical (read from tmp, write to rgba) ---
-    for y in 0..h {
-        for x in 0..w {
-            let ci = (y * w + x) * 4;
-            let cr = tmp[ci] as i32;
-            let cg = tmp[ci + 1] as i32;
-            let cb = tmp[ci + 2] as i32;
+/// Exact (non-separable) bilateral filter over the full (2r+1)² window.
+#[allow(clippy::too_many_arguments)]
+pub fn bilateral_full(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    radius: usize,
+    sigma_s: f32,
+    sigma_r: f32,
+    metric: RangeMetric,
+    radius_cap: usize,
+) {
+    let (r, sigma_s) = resolve_radius(radius, radius_cap, sigma_s);
+    let r = r as isize;
+    let kernel_len = (2 * r + 1) as usize;
+
+    // 2D spatial LUT; corners beyond the radius are dropped (circular window)
+    let inv_2ss = -0.5 / (sigma_s * sigma_s);
+    let mut spatial_w = vec![0.0f32; kernel_len * kernel_len];
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let d2 = (dx * dx + dy * dy) as f32;
+            if d2 <= (r * r) as f32 {
+                spatial_w[((dy + r) as usize) * kernel_len + (dx + r) as usize] =
+                    (inv_2ss * d2).exp();
+            }
+        }
+    }
+    let range_w = range_lut(sigma_r);
+
+    let src = rgba.to_vec();
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            let ci = (y as usize * w + x as usize) * 4;
+            let c = [src[ci] as i32, src[ci + 1] as i32, src[ci + 2] as i32];
 
             let mut sr = 0.0f32;
             let mut sg = 0.0f32;
             let mut sb = 0.0f32;
             let mut sw = 0.0f32;
 
-            #[allow(clippy::needless_range_loop)]
-            for ki in 0..kernel_len {
-                let sy = y as isize + ki as isize - ri;
+            for dy in -r..=r {
+                let sy = y + dy;
                 if sy < 0 || sy >= h as isize {
                     continue;
                 }
-                let ni = (sy as usize * w + x) * 4;
-                let nr = tmp[ni] as i32;
-                let ng = tmp[ni + 1] as i32;
-                let nb = tmp[ni + 2] as i32;
-
-                let diff = (cr - nr)
-                    .unsigned_abs()
-                    .max((cg - ng).unsigned_abs())
-                    .max((cb - nb).unsigned_abs()) as usize;
-
-                let wt = spatial_w[ki] * range_w[diff.min(255)];
-                sr += nr as f32 * wt;
-                sg += ng as f32 * wt;
-                sb += nb as f32 * wt;
-                sw += wt;
+                let krow = ((dy + r) as usize) * kernel_len;
+                for dx in -r..=r {
+                    let sx = x + dx;
+                    let spatial = spatial_w[krow + (dx + r) as usize];
+                    if sx < 0 || sx >= w as isize || spatial == 0.0 {
+                        continue;
+                    }
+                    let ni = (sy as usize * w + sx as usize) * 4;
+                    let n = [src[ni] as i32, src[ni + 1] as i32, src[ni + 2] as i32];
+
+                    let diff = metric.distance(c, n);
+                    let wt = spatial * range_w[diff.min(RANGE_LUT_LEN - 1)];
+                    sr += n[0] as f32 * wt;
+                    sg += n[1] as f32 * wt;
+                    sb += n[2] as f32 * wt;
+                    sw += wt;
+                }
             }
 
             if sw > 0.0 {
                 let inv = 1.0 / sw;
-                rgba[ci] = (sr * inv) as u8;
-                rgba[ci + 1] = (sg * inv) as u8;
-                rgba[ci + 2] = (sb * inv) as u8;
+                rgba[ci] = (sr * inv + 0.5) as u8;
+                rgba[ci + 1] = (sg * inv + 0.5) as u8;
+                rgba[ci + 2] = (sb * inv + 0.5) as u8;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Filter = fn(&mut [u8], usize, usize, usize, f32, f32, RangeMetric, usize);
+    const FILTERS: [Filter; 2] = [bilateral_separable, bilateral_full];
+
+    /// Gray `w × h` image from a per-pixel value.
+    fn gray(w: usize, h: usize, mut v: impl FnMut(usize, usize) -> u8) -> Vec<u8> {
+        (0..w * h)
+            .flat_map(|i| {
+                let g = v(i % w, i / w);
+                [g, g, g, 255]
+            })
+            .collect()
+    }
+
+    /// Deterministic uniform noise in ±`amp` around `base`.
+    fn noisy(w: usize, h: usize, base: i32, amp: i32) -> Vec<u8> {
+        let mut state = 7u32;
+        gray(w, h, |_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (base + ((state >> 16) % (2 * amp as u32 + 1)) as i32 - amp) as u8
+        })
+    }
+
+    fn variance(rgba: &[u8]) -> f32 {
+        let n = (rgba.len() / 4) as f32;
+        let mean = rgba.chunks_exact(4).map(|p| p[0] as f32).sum::<f32>() / n;
+        rgba.chunks_exact(4)
+            .map(|p| (p[0] as f32 - mean).powi(2))
+            .sum::<f32>()
+            / n
+    }
+
+    #[test]
+    fn test_flat_image_unchanged() {
+        for filter in FILTERS {
+            let rgba = gray(16, 16, |_, _| 120);
+            let mut buf = rgba.clone();
+            filter(&mut buf, 16, 16, 3, 0.0, 30.0, RangeMetric::Max, 7);
+            assert_eq!(buf, rgba);
+        }
+    }
+
+    #[test]
+    fn test_noise_is_reduced() {
+        for filter in FILTERS {
+            let rgba = noisy(32, 32, 128, 15);
+            let mut buf = rgba.clone();
+            filter(&mut buf, 32, 32, 3, 0.0, 30.0, RangeMetric::Euclidean, 7);
+            let (before, after) = (variance(&rgba), variance(&buf));
+            assert!(after < before * 0.4, "{before} → {after}");
+        }
+    }
+
+    #[test]
+    fn test_edge_is_kept() {
+        for filter in FILTERS {
+            let mut buf = gray(16, 16, |x, _| if x < 8 { 30 } else { 220 });
+            filter(&mut buf, 16, 16, 3, 0.0, 20.0, RangeMetric::Luma, 7);
+            let row = 8 * 16;
+            assert!(buf[(row + 7) * 4] <= 32, "{}", buf[(row + 7) * 4]);
+            assert!(buf[(row + 8) * 4] >= 218, "{}", buf[(row + 8) * 4]);
+        }
+    }
+}
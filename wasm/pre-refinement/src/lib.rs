@@ -1,14 +1,22 @@
+mod analysis;
+mod auto_color;
 mod clahe;
+mod color;
 mod denoise;
+mod options;
 mod sharpen;
 
+use options::PreProcessOptions;
 use wasm_bindgen::prelude::*;
 
 /// High-performance pre-processing pipeline.
 ///
-/// Pipeline: Bilateral Denoise → CLAHE → Unsharp-Mask Sharpen
+/// Pipeline: [Auto Color] → Bilateral Denoise → CLAHE → Unsharp-Mask Sharpen
 /// All in-place on a single buffer. Zero redundant allocations.
 /// Every hot loop is branch-free and cache-line aligned.
+///
+/// Every other setting keeps its `PreProcessOptions` default; see
+/// `pre_process_with_options` for the full set.
 #[wasm_bindgen]
 pub fn pre_process(
     rgba: &[u8],
@@ -19,6 +27,34 @@ pub fn pre_process(
     denoise_radius: u32,
     sharpen_strength: f32,
 ) -> Vec<u8> {
+    let options =
+        PreProcessOptions::with_basics(clahe_clip, clahe_grid, denoise_radius, sharpen_strength);
+    pre_process_rgba(rgba, width, height, &options)
+}
+
+/// `pre_process` with every setting in one object.
+///
+/// `options` is the worker's `PreProcessingConfig` object, passed as is; see
+/// `PreProcessOptions` for the keys and their defaults. Denoising uses
+/// `denoiseMethod`: "bilateral" (separable), "bilateral_full" (exact, no
+/// diagonal streaks, O(r²) per pixel) or "nlm" (non-local means, keeps fine
+/// texture, O(S²) per pixel). The bilateral paths run when
+/// `noiseKernelSize` > 0, NLM when `nlmH` and `nlmSearchRadius` are both
+/// positive.
+///
+/// Errors if `options` is not an object of the expected shape.
+#[wasm_bindgen]
+pub fn pre_process_with_options(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    options: JsValue,
+) -> Result<Vec<u8>, JsError> {
+    let options: PreProcessOptions = serde_wasm_bindgen::from_value(options)?;
+    Ok(pre_process_rgba(rgba, width, height, &options))
+}
+
+fn pre_process_rgba(rgba: &[u8], width: u32, height: u32, options: &PreProcessOptions) -> Vec<u8> {
     let w = width as usize;
     let h = height as usize;
     if rgba.len() != w * h * 4 {
@@ -27,21 +63,226 @@ pub fn pre_process(
 
     let mut buf = rgba.to_vec();
 
-    // Stage 1: Edge-preserving bilateral denoise
-    // Uses separable approximation: H-pass then V-pass (O(w*h*r) instead of O(w*h*r²))
-    if denoise_radius > 0 {
-        denoise::bilateral_separable(&mut buf, w, h, denoise_radius as usize);
+    // Stage 0: Color cast and tonal range correction
+    auto_color::apply_white_balance(
+        &mut buf,
+        auto_color::WhiteBalance::from_name(&options.white_balance),
+        1.0,
+    );
+    if options.auto_levels_clip > 0.0 {
+        auto_color::apply_auto_levels(&mut buf, options.auto_levels_clip, false);
+    }
+
+    let (denoise_radius, nlm_h, nlm_search_radius, clahe_clip, clahe_grid, sharpen_strength) =
+        if options.auto_params {
+            let rec = analysis::recommend(&analysis::analyze(&buf, w, h), w, h);
+            (
+                rec.denoise_radius,
+                rec.nlm_h,
+                rec.nlm_search_radius,
+                rec.clahe_clip,
+                rec.clahe_grid,
+                rec.sharpen_strength,
+            )
+        } else {
+            (
+                options.noise_kernel_size,
+                options.nlm_h,
+                options.nlm_search_radius,
+                options.clahe_clip_limit,
+                options.clahe_grid_size,
+                options.sharpen_strength,
+            )
+        };
+
+    // Stage 1: Edge-preserving denoise (bilateral or non-local means)
+    // NLM has its own window and strength; the bilateral paths use the radius.
+    // Separable approximation by default: H-pass then V-pass (O(w*h*r) instead of O(w*h*r²))
+    if options.denoise_method == "nlm" {
+        if nlm_h > 0.0 && nlm_search_radius > 0 {
+            imaging::nlm::nlm_denoise(
+                &mut buf,
+                w,
+                h,
+                nlm_search_radius as usize,
+                options.nlm_patch_radius as usize,
+                nlm_h,
+            );
+        }
+    } else if denoise_radius > 0 {
+        let metric = denoise::RangeMetric::from_name(&options.denoise_range_metric);
+        let radius = denoise_radius as usize;
+        let cap = options.denoise_radius_cap as usize;
+        match options.denoise_method.as_str() {
+            "bilateral_full" => denoise::bilateral_full(
+                &mut buf,
+                w,
+                h,
+                radius,
+                options.denoise_sigma_spatial,
+                options.denoise_sigma_range,
+                metric,
+                cap,
+            ),
+            _ => denoise::bilateral_separable(
+                &mut buf,
+                w,
+                h,
+                radius,
+                options.denoise_sigma_spatial,
+                options.denoise_sigma_range,
+                metric,
+                cap,
+            ),
+        }
     }
 
     // Stage 2: CLAHE with interpolated tile CDFs
     if clahe_clip > 1.0 && clahe_grid >= 2 {
-        clahe::apply_clahe(&mut buf, w, h, clahe_clip, clahe_grid as usize);
+        clahe::apply_clahe(
+            &mut buf,
+            w,
+            h,
+            clahe_clip,
+            clahe_grid as usize,
+            clahe::ClaheSpace::from_name(&options.clahe_color_space),
+            options.clahe_strength,
+        );
     }
 
-    // Stage 3: Unsharp mask via box-blur difference (faster than Laplacian, better quality)
+    // Stage 3: Unsharp mask against an approximated Gaussian (no Laplacian ringing)
     if sharpen_strength > 0.0 {
-        sharpen::unsharp_mask(&mut buf, w, h, sharpen_strength);
+        sharpen::unsharp_mask(
+            &mut buf,
+            w,
+            h,
+            sharpen_strength,
+            options.sharpen_radius,
+            options.sharpen_threshold,
+            sharpen::SharpenMode::from_name(&options.sharpen_mode),
+        );
+    }
+
+    buf
+}
+
+/// General-purpose unsharp mask, e.g. after downscaling.
+///
+/// - `amount`: detail gain (0.5–1.5 typical)
+/// - `radius`: Gaussian sigma in pixels
+/// - `threshold`: minimum detail, in 0–255 levels, that gets sharpened
+/// - `mode`: "usm" or "edges" (smart sharpen, flat areas untouched)
+#[wasm_bindgen]
+pub fn sharpen(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    amount: f32,
+    radius: f32,
+    threshold: f32,
+    mode: &str,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+
+    let mut buf = rgba.to_vec();
+    sharpen::unsharp_mask(
+        &mut buf,
+        w,
+        h,
+        amount,
+        radius,
+        threshold,
+        sharpen::SharpenMode::from_name(mode),
+    );
+    buf
+}
+
+/// Remove a color cast with a diagonal (per-channel) gain in linear light.
+///
+/// - `method`: "gray_world" (average is gray), "white_patch" (brightest 1%
+///   is white) or "combined" (geometric mean of both, the most robust)
+/// - `strength`: 0 (unchanged) … 1 (full correction)
+///
+/// Transparent pixels are ignored when measuring the cast.
+#[wasm_bindgen]
+pub fn auto_white_balance(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    method: &str,
+    strength: f32,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
+    }
+
+    let mut buf = rgba.to_vec();
+    auto_color::apply_white_balance(
+        &mut buf,
+        auto_color::WhiteBalance::from_name(method),
+        strength,
+    );
+    buf
+}
+
+/// Auto levels / auto contrast: stretch the range between the `clip_percent`
+/// and 100 − `clip_percent` percentiles to 0–255.
+///
+/// `per_channel`: stretch R, G and B independently (also neutralizes casts)
+/// instead of applying the luma stretch to all channels.
+#[wasm_bindgen]
+pub fn auto_levels(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    clip_percent: f32,
+    per_channel: bool,
+) -> Vec<u8> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 {
+        return rgba.to_vec();
     }
 
+    let mut buf = rgba.to_vec();
+    auto_color::apply_auto_levels(&mut buf, clip_percent, per_channel);
     buf
 }
+
+/// Estimate noise, contrast and sharpness and recommend `pre_process` parameters.
+///
+/// Returns `[noise_sigma, contrast, sharpness, denoise_radius, clahe_clip,
+/// clahe_grid, sharpen_strength, nlm_h, nlm_search_radius]`:
+/// - `noise_sigma`: luma noise standard deviation, 0–255 units
+/// - `contrast`: RMS contrast, std(luma) / 255
+/// - `sharpness`: 0 (soft) to ~0.67 (pixel-sharp edges)
+///
+/// Empty on invalid input.
+#[wasm_bindgen]
+pub fn analyze_image(rgba: &[u8], width: u32, height: u32) -> Vec<f32> {
+    let w = width as usize;
+    let h = height as usize;
+    if rgba.len() != w * h * 4 || w == 0 || h == 0 {
+        return Vec::new();
+    }
+
+    let stats = analysis::analyze(rgba, w, h);
+    let rec = analysis::recommend(&stats, w, h);
+    vec![
+        stats.noise_sigma,
+        stats.contrast,
+        stats.sharpness,
+        rec.denoise_radius as f32,
+        rec.clahe_clip,
+        rec.clahe_grid as f32,
+        rec.sharpen_strength,
+        rec.nlm_h,
+        rec.nlm_search_radius as f32,
+    ]
+}
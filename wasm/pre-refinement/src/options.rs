@@ -0,0 +1,103 @@
+use serde::Deserialize;
+
+/// `pre_process` settings, deserialized from the worker's
+/// `PreProcessingConfig` object (camelCase keys, the same names as in
+/// types.ts). Missing keys take the TypeScript defaults; keys only the
+/// worker reads, such as `enabled`, are ignored.
+///
+/// Mode strings are parsed by each stage's `from_name`, so an unknown value
+/// falls back to that stage's default mode.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PreProcessOptions {
+    /// "none", "gray_world", "white_patch" or "combined".
+    pub white_balance: String,
+    /// Percent clipped at each end by auto levels (0 = off).
+    pub auto_levels_clip: f32,
+
+    pub clahe_clip_limit: f32,
+    pub clahe_grid_size: u32,
+    /// "luma", "oklab", "lab" or "hsv".
+    pub clahe_color_space: String,
+    /// Blend with the unequalized image (0–1).
+    pub clahe_strength: f32,
+
+    /// Bilateral radius; NLM ignores it.
+    pub noise_kernel_size: u32,
+    /// Spatial sigma in pixels (0 = the radius).
+    pub denoise_sigma_spatial: f32,
+    /// Range sigma in 0–255 units.
+    pub denoise_sigma_range: f32,
+    /// "max" (per-channel), "euclidean" (RGB) or "luma".
+    pub denoise_range_metric: String,
+    /// Upper bound on `noise_kernel_size`.
+    pub denoise_radius_cap: u32,
+    /// "bilateral", "bilateral_full" or "nlm".
+    pub denoise_method: String,
+
+    /// NLM search window radius S (5–10).
+    pub nlm_search_radius: u32,
+    /// NLM compared patch radius (1–3).
+    pub nlm_patch_radius: u32,
+    /// NLM filtering strength in 0–255 units, ~1–1.5× the noise sigma.
+    pub nlm_h: f32,
+
+    /// Unsharp-mask amount.
+    pub sharpen_strength: f32,
+    /// Gaussian sigma in pixels (0.8 ≈ the old 3×3 box).
+    pub sharpen_radius: f32,
+    /// Detail below this many levels is left alone.
+    pub sharpen_threshold: f32,
+    /// "usm" or "edges" (restricted to edges).
+    pub sharpen_mode: String,
+
+    /// Replace the denoise radius, the NLM strength and search radius, CLAHE
+    /// clip and grid and the sharpen amount with the values `analyze_image`
+    /// recommends.
+    pub auto_params: bool,
+}
+
+impl PreProcessOptions {
+    /// Defaults with the four settings of the positional `pre_process` export.
+    pub fn with_basics(
+        clahe_clip_limit: f32,
+        clahe_grid_size: u32,
+        noise_kernel_size: u32,
+        sharpen_strength: f32,
+    ) -> Self {
+        PreProcessOptions {
+            clahe_clip_limit,
+            clahe_grid_size,
+            noise_kernel_size,
+            sharpen_strength,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for PreProcessOptions {
+    fn default() -> Self {
+        PreProcessOptions {
+            white_balance: "none".into(),
+            auto_levels_clip: 0.0,
+            clahe_clip_limit: 2.0,
+            clahe_grid_size: 8,
+            clahe_color_space: "luma".into(),
+            clahe_strength: 1.0,
+            noise_kernel_size: 3,
+            denoise_sigma_spatial: 0.0,
+            denoise_sigma_range: 30.0,
+            denoise_range_metric: "max".into(),
+            denoise_radius_cap: 7,
+            denoise_method: "bilateral".into(),
+            nlm_search_radius: 7,
+            nlm_patch_radius: 1,
+            nlm_h: 10.0,
+            sharpen_strength: 0.3,
+            sharpen_radius: 0.8,
+            sharpen_threshold: 0.0,
+            sharpen_mode: "usm".into(),
+            auto_params: false,
+        }
+    }
+}
@@ -1,94 +1,75 @@
-/// Unsharp Mask — faster and better quality than Laplacian sharpening.
-///
-/// Strategy: sharp = original + strength * (original - blur(original))
-/// Uses separable box blur (running sum, O(1) per pixel) for the blur component.
-///
-/// Advantages over Laplacian:
-/// - No ring artifacts at edges
-/// - Tunable radius via box blur
-/// - Same or faster speed (running sum vs 3x3 convolution)
-///
-/// Uses luminance-proportional scaling to preserve color ratios.
+use imaging::blur::{box_blur, gaussian_blur};
+
+/// BT.601 luma, 0–255.
 #[inline(always)]
 fn rgb_to_lum_f32(r: u8, g: u8, b: u8) -> f32 {
     r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114
 }
 
-pub fn unsharp_mask(rgba: &mut [u8], w: usize, h: usize, strength: f32) {
-    if h < 3 || w < 3 {
+/// Sobel gradient (0–255 slope units) where the edge mask starts / saturates.
+const EDGE_LOW: f32 = 4.0;
+const EDGE_HIGH: f32 = 16.0;
+
+/// Where `unsharp_mask` applies the detail.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SharpenMode {
+    /// Everywhere the detail passes the threshold (classic unsharp mask).
+    Usm,
+    /// Only along edges found by a smoothed Sobel mask ("smart sharpen").
+    Edges,
+}
+
+impl SharpenMode {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "edges" => SharpenMode::Edges,
+            _ => SharpenMode::Usm,
+        }
+    }
+}
+
+/// Unsharp mask of `rgba` in place — the standard amount / radius / threshold
+/// sharpen.
+///
+/// Strategy: sharp = original + amount * (original - gaussian(original, radius))
+/// on luminance, with a soft threshold so flat areas (where the detail signal
+/// is mostly noise) are left alone.
+///
+/// - Gaussian approximated by three box blurs (`imaging::blur`), each a
+///   running sum — O(1) per pixel whatever the radius
+/// - Threshold: detail below `threshold` levels is ignored, and fades in up
+///   to 2× the threshold (no hard on/off seams)
+/// - `SharpenMode::Edges` ("smart sharpen"): detail is further weighted by a
+///   smoothed Sobel edge mask, so only real edges get crisper
+///
+/// Uses luminance-proportional scaling to preserve color ratios.
+pub fn unsharp_mask(
+    rgba: &mut [u8],
+    w: usize,
+    h: usize,
+    amount: f32,
+    radius: f32,
+    threshold: f32,
+    mode: SharpenMode,
+) {
+    if h < 3 || w < 3 || amount <= 0.0 {
         return;
     }
 
     let npx = w * h;
-    let radius = 1usize; // Small radius for detail sharpening
 
     // Step 1: Extract luminance
-    let mut lum = vec![0.0f32; npx];
-    #[allow(clippy::needless_range_loop)]
-    for i in 0..npx {
-        let off = i * 4;
-        lum[i] = rgb_to_lum_f32(rgba[off], rgba[off + 1], rgba[off + 2]);
-    }
+    let lum: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|p| rgb_to_lum_f32(p[0], p[1], p[2]))
+        .collect();
 
-    // Step 2: Box blur luminance (separable, running sum)
-    // Horizontal pass
-    let mut blur_h = vec![0.0f32; npx];
-    let r = radius as isize;
-    for y in 0..h {
-        let row = y * w;
-        let mut sum = 0.0f32;
-        let mut count = 0i32;
-
-        // Init window
-        for x in 0..=(radius.min(w - 1)) {
-            sum += lum[row + x];
-            count += 1;
-        }
-        blur_h[row] = sum / count as f32;
-
-        for x in 1..w {
-            let add_x = x + radius;
-            if add_x < w {
-                sum += lum[row + add_x];
-                count += 1;
-            }
-            let rem_x = x as isize - r - 1;
-            if rem_x >= 0 {
-                sum -= lum[row + rem_x as usize];
-                count -= 1;
-            }
-            blur_h[row + x] = sum / count as f32;
-        }
-    }
+    // Step 2: Gaussian blur of luminance
+    let blurred = gaussian_blur(&lum, w, h, radius.max(0.1));
 
-    // Vertical pass
-    let mut blurred = vec![0.0f32; npx];
-    for x in 0..w {
-        let mut sum = 0.0f32;
-        let mut count = 0i32;
+    let edge_mask = (mode == SharpenMode::Edges).then(|| edge_mask(&blurred, w, h));
 
-        for y in 0..=(radius.min(h - 1)) {
-            sum += blur_h[y * w + x];
-            count += 1;
-        }
-        blurred[x] = sum / count as f32;
-
-        for y in 1..h {
-            let add_y = y + radius;
-            if add_y < h {
-                sum += blur_h[add_y * w + x];
-                count += 1;
-            }
-            let rem_y = y as isize - r - 1;
-            if rem_y >= 0 {
-                sum -= blur_h[rem_y as usize * w + x];
-                count -= 1;
-            }
-            blurred[y * w + x] = sum / count as f32;
-        }
-    }
-
-    // Step 3: Apply unsharp mask: new_lum = lum + strength * (lum - blurred)
+    // Step 3: Apply unsharp mask: new_lum = lum + amount * (lum - blurred)
     // Use luminance ratio to scale RGB channels proportionally
     for i in 0..npx {
         let old_lum = lum[i];
@@ -96,8 +77,17 @@ pub fn unsharp_mask(rgba: &mut [u8], w: usize, h: usize, strength: f32) {
             continue;
         }
 
-        let detail = old_lum - blurred[i];
-        let new_lum = (old_lum + strength * detail).clamp(0.0, 255.0);
+        let mut detail = old_lum - blurred[i];
+        if threshold > 0.0 {
+            detail *= ((detail.abs() - threshold) / threshold).clamp(0.0, 1.0);
+        }
+        if let Some(mask) = &edge_mask {
+            detail *= mask[i];
+        }
+        if detail == 0.0 {
+            continue;
+        }
+        let new_lum = (old_lum + amount * detail).clamp(0.0, 255.0);
 
         // Fixed-point scale: (new_lum << 16) / old_lum
         let scale_fp = ((new_lum as u32) << 16) / (old_lum as u32).max(1);
@@ -107,3 +97,78 @@ pub fn unsharp_mask(rgba: &mut [u8], w: usize, h: usize, strength: f32) {
         rgba[off + 2] = ((rgba[off + 2] as u32 * scale_fp) >> 16).min(255) as u8;
     }
 }
+
+/// 0–1 edge weight from the Sobel magnitude of the (already smoothed) luma,
+/// ramped between `EDGE_LOW` and `EDGE_HIGH` and box-smoothed so halos
+/// on both sides of an edge are covered.
+fn edge_mask(lum: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let mut mask = vec![0.0f32; w * h];
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let i = y * w + x;
+            let gx = (lum[i - w + 1] + 2.0 * lum[i + 1] + lum[i + w + 1])
+                - (lum[i - w - 1] + 2.0 * lum[i - 1] + lum[i + w - 1]);
+            let gy = (lum[i + w - 1] + 2.0 * lum[i + w] + lum[i + w + 1])
+                - (lum[i - w - 1] + 2.0 * lum[i - w] + lum[i - w + 1]);
+            let g = (gx * gx + gy * gy).sqrt() * 0.125;
+            mask[i] = ((g - EDGE_LOW) / (EDGE_HIGH - EDGE_LOW)).clamp(0.0, 1.0);
+        }
+    }
+    box_blur(&mask, w, h, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray `w × h` image from a per-pixel value.
+    fn gray(w: usize, h: usize, v: impl Fn(usize, usize) -> u8) -> Vec<u8> {
+        (0..w * h)
+            .flat_map(|i| {
+                let g = v(i % w, i / w);
+                [g, g, g, 255]
+            })
+            .collect()
+    }
+
+    fn step(x: usize, _: usize) -> u8 {
+        if x < 10 {
+            80
+        } else {
+            160
+        }
+    }
+
+    #[test]
+    fn test_zero_amount_is_identity() {
+        let rgba = gray(20, 12, step);
+        let mut buf = rgba.clone();
+        unsharp_mask(&mut buf, 20, 12, 0.0, 1.5, 0.0, SharpenMode::Usm);
+        assert_eq!(buf, rgba);
+    }
+
+    #[test]
+    fn test_detail_under_threshold_is_kept() {
+        // ±3-level texture on a flat gray, threshold 5
+        let rgba = gray(20, 12, |x, y| if (x + y) % 2 == 0 { 117 } else { 123 });
+        for mode in [SharpenMode::Usm, SharpenMode::Edges] {
+            let mut buf = rgba.clone();
+            unsharp_mask(&mut buf, 20, 12, 1.0, 1.0, 5.0, mode);
+            assert_eq!(buf, rgba);
+        }
+    }
+
+    #[test]
+    fn test_edge_contrast_increases() {
+        for mode in [SharpenMode::Usm, SharpenMode::Edges] {
+            let mut buf = gray(20, 12, step);
+            unsharp_mask(&mut buf, 20, 12, 1.0, 1.0, 0.0, mode);
+            let row = 6 * 20;
+            // Overshoot on both sides of the edge, flat far away
+            assert!(buf[(row + 9) * 4] < 80, "{}", buf[(row + 9) * 4]);
+            assert!(buf[(row + 10) * 4] > 160, "{}", buf[(row + 10) * 4]);
+            assert_eq!(buf[(row + 1) * 4], 80);
+            assert_eq!(buf[(row + 18) * 4], 160);
+        }
+    }
+}